@@ -1,3 +1,45 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Files whose changes mean the commit hash may have changed.
+const GIT_PATHS: [&str; 2] = ["../.git/HEAD", "../.git/refs/heads"];
+
 fn main() {
+  let commit = Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+    .map(|hash| hash.trim().to_string())
+    .filter(|hash| !hash.is_empty())
+    .unwrap_or_else(|| "unknown".to_string());
+  // Reproducible builds pin the timestamp through SOURCE_DATE_EPOCH.
+  let built_at = env::var("SOURCE_DATE_EPOCH")
+    .ok()
+    .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+    .unwrap_or_else(|| {
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+    });
+
+  println!("cargo:rustc-env=ARK_DROP_GIT_COMMIT={}", commit);
+  println!("cargo:rustc-env=ARK_DROP_BUILD_TIMESTAMP={}", built_at);
+  println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+  // A missing path would make Cargo rerun this script, and so rebuild the
+  // app with a new timestamp, on every build. That is the case for source
+  // tarballs and for worktrees or submodules, where .git is a file.
+  for path in GIT_PATHS {
+    if Path::new(path).exists() {
+      println!("cargo:rerun-if-changed={}", path);
+    }
+  }
+  // Set by `cargo fuzz`, which builds ticket.rs on its own.
+  println!("cargo:rustc-check-cfg=cfg(fuzzing)");
+
   tauri_build::build()
 }
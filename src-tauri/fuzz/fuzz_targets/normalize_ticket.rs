@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The command wrapper in ticket.rs is compiled out under `cfg(fuzzing)`, so
+// the parser builds without tauri.
+#[path = "../../src/ticket.rs"]
+mod ticket;
+
+fuzz_target!(|data: &[u8]| {
+  let Ok(input) = std::str::from_utf8(data) else {
+    return;
+  };
+  // Accepted tickets are already canonical, so normalizing them again must
+  // give the same result.
+  if let Ok(normalized) = ticket::normalize(input) {
+    assert_eq!(ticket::normalize(&normalized), Ok(normalized.clone()));
+  }
+});
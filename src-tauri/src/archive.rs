@@ -0,0 +1,378 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use zip::ZipArchive;
+
+/// Listing stops after this many entries so a crafted archive can't make us
+/// enumerate millions of headers.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Zip archives whose central directory is larger than this are rejected
+/// before it is parsed, since the zip reader loads all of it up front.
+const MAX_ZIP_DIRECTORY_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Zip archives declaring more entries than this are rejected for the same
+/// reason.
+const MAX_ZIP_DIRECTORY_ENTRIES: u64 = 100_000;
+
+/// Listing a gzipped tar stops after decompressing this much, as skipping
+/// over an entry means inflating all of its data.
+const MAX_DECOMPRESSED_BYTES: u64 = 1024 * 1024 * 1024;
+
+const ZIP_END_SIGNATURE: &[u8] = b"PK\x05\x06";
+const ZIP64_LOCATOR_SIGNATURE: &[u8] = b"PK\x06\x07";
+const ZIP64_END_SIGNATURE: &[u8] = b"PK\x06\x06";
+
+/// One entry of an archive as shown when peeking into a received file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+  pub name: String,
+  pub is_dir: bool,
+  /// Uncompressed size in bytes.
+  pub size: u64,
+  /// Size stored in the archive, when the format records it per entry.
+  pub compressed_size: Option<u64>,
+  pub compression: String,
+}
+
+/// The entries of an archive, possibly cut short.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveListing {
+  pub entries: Vec<ArchiveEntry>,
+  /// Set when the archive holds more than could be listed, so `entries` is
+  /// incomplete.
+  pub truncated: bool,
+}
+
+enum ArchiveKind {
+  Zip,
+  Tar,
+  TarGz,
+}
+
+fn detect(file: &mut File) -> io::Result<Option<ArchiveKind>> {
+  let mut header = [0u8; 262];
+  let read = read_up_to(file, &mut header)?;
+  file.seek(SeekFrom::Start(0))?;
+  let header = &header[..read];
+
+  let kind = if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+    Some(ArchiveKind::Zip)
+  } else if header.starts_with(&[0x1f, 0x8b]) {
+    Some(ArchiveKind::TarGz)
+  } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+    Some(ArchiveKind::Tar)
+  } else {
+    None
+  };
+  Ok(kind)
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+  let mut filled = 0;
+  while filled < buf.len() {
+    match reader.read(&mut buf[filled..])? {
+      0 => break,
+      read => filled += read,
+    }
+  }
+  Ok(filled)
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u64 {
+  u16::from_le_bytes([bytes[at], bytes[at + 1]]).into()
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u64 {
+  let mut buf = [0; 4];
+  buf.copy_from_slice(&bytes[at..at + 4]);
+  u32::from_le_bytes(buf).into()
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+  let mut buf = [0; 8];
+  buf.copy_from_slice(&bytes[at..at + 8]);
+  u64::from_le_bytes(buf)
+}
+
+/// Reads the entry count and size of the central directory from the end of
+/// the archive, without parsing the directory itself.
+fn zip_directory_size(file: &mut File) -> io::Result<Option<(u64, u64)>> {
+  // End record (22 bytes) plus the longest possible comment, and room for
+  // the zip64 locator in front of it.
+  let len = file.metadata()?.len();
+  let tail_len = len.min(20 + 22 + u64::from(u16::MAX));
+  let mut tail = vec![0; tail_len as usize];
+  file.seek(SeekFrom::Start(len - tail_len))?;
+  file.read_exact(&mut tail)?;
+
+  let end = match (0..tail.len().saturating_sub(21))
+    .rev()
+    .find(|&at| tail[at..].starts_with(ZIP_END_SIGNATURE))
+  {
+    Some(end) => end,
+    None => return Ok(None),
+  };
+  let entries = read_u16(&tail, end + 8).max(read_u16(&tail, end + 10));
+  let size = read_u32(&tail, end + 12);
+  if entries != u64::from(u16::MAX) && size != u64::from(u32::MAX) {
+    return Ok(Some((entries, size)));
+  }
+
+  // Zip64 stores the real values in a second end record, found through the
+  // locator right before the first one.
+  let locator = match end.checked_sub(20) {
+    Some(locator) if tail[locator..].starts_with(ZIP64_LOCATOR_SIGNATURE) => locator,
+    _ => return Ok(None),
+  };
+  let mut record = [0; 56];
+  file.seek(SeekFrom::Start(read_u64(&tail, locator + 8)))?;
+  if read_up_to(file, &mut record)? < record.len() || !record.starts_with(ZIP64_END_SIGNATURE) {
+    return Ok(None);
+  }
+  let entries = read_u64(&record, 24).max(read_u64(&record, 32));
+  // The reader parses from the directory offset onwards, so measure from
+  // there rather than trusting the recorded size.
+  let size = read_u64(&record, 40).max(len.saturating_sub(read_u64(&record, 48)));
+  Ok(Some((entries, size)))
+}
+
+fn list_zip(mut file: File) -> Result<ArchiveListing, String> {
+  let (entry_count, directory_bytes) = zip_directory_size(&mut file)
+    .map_err(|error| format!("Corrupt zip archive: {}", error))?
+    .ok_or_else(|| "Corrupt zip archive: central directory not found".to_string())?;
+  if entry_count > MAX_ZIP_DIRECTORY_ENTRIES || directory_bytes > MAX_ZIP_DIRECTORY_BYTES {
+    return Err("Zip archive directory is too large to list".to_string());
+  }
+  file.seek(SeekFrom::Start(0)).map_err(|error| error.to_string())?;
+
+  let mut archive = ZipArchive::new(BufReader::new(file))
+    .map_err(|error| format!("Corrupt zip archive: {}", error))?;
+
+  let mut entries = Vec::new();
+  for index in 0..archive.len().min(MAX_ENTRIES) {
+    let entry = archive
+      .by_index_raw(index)
+      .map_err(|error| format!("Corrupt zip archive: {}", error))?;
+    entries.push(ArchiveEntry {
+      name: entry.name().to_string(),
+      is_dir: entry.is_dir(),
+      size: entry.size(),
+      compressed_size: Some(entry.compressed_size()),
+      compression: entry.compression().to_string(),
+    });
+  }
+  Ok(ArchiveListing {
+    entries,
+    truncated: archive.len() > MAX_ENTRIES,
+  })
+}
+
+/// Appends tar entries to `listed` until [`MAX_ENTRIES`] is reached. Returns
+/// whether more entries were left.
+fn collect_tar<R: Read>(
+  iter: tar::Entries<'_, R>,
+  compression: &str,
+  listed: &mut Vec<ArchiveEntry>,
+) -> Result<bool, String> {
+  for entry in iter {
+    if listed.len() == MAX_ENTRIES {
+      return Ok(true);
+    }
+    let entry = entry.map_err(|error| format!("Corrupt tar archive: {}", error))?;
+    let header = entry.header();
+    listed.push(ArchiveEntry {
+      name: entry
+        .path()
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(|error| format!("Corrupt tar archive: {}", error))?,
+      is_dir: header.entry_type().is_dir(),
+      size: entry.size(),
+      compressed_size: None,
+      compression: compression.to_string(),
+    });
+  }
+  Ok(false)
+}
+
+fn list_tar(file: File) -> Result<ArchiveListing, String> {
+  // Seeking past entry data avoids reading the whole file.
+  let mut archive = tar::Archive::new(BufReader::new(file));
+  let iter = archive
+    .entries_with_seek()
+    .map_err(|error| format!("Corrupt tar archive: {}", error))?;
+  let mut entries = Vec::new();
+  let truncated = collect_tar(iter, "none", &mut entries)?;
+  Ok(ArchiveListing { entries, truncated })
+}
+
+fn list_tar_gz(file: File) -> Result<ArchiveListing, String> {
+  let reader = GzDecoder::new(BufReader::new(file)).take(MAX_DECOMPRESSED_BYTES);
+  let mut archive = tar::Archive::new(reader);
+  let mut entries = Vec::new();
+  let result = archive
+    .entries()
+    .map_err(|error| format!("Corrupt tar archive: {}", error))
+    .and_then(|iter| collect_tar(iter, "gzip", &mut entries));
+
+  // Running out of budget looks like a truncated archive to the tar reader;
+  // keep what was listed up to that point.
+  if archive.into_inner().limit() == 0 {
+    return Ok(ArchiveListing {
+      entries,
+      truncated: true,
+    });
+  }
+  let truncated = result?;
+  Ok(ArchiveListing { entries, truncated })
+}
+
+/// Lists the entries of a zip, tar or gzipped tar file without extracting it.
+pub fn list_entries(path: &Path) -> Result<ArchiveListing, String> {
+  let mut file = File::open(path).map_err(|error| error.to_string())?;
+  let kind = detect(&mut file).map_err(|error| error.to_string())?;
+
+  let listing = match kind {
+    Some(ArchiveKind::Zip) => list_zip(file)?,
+    Some(ArchiveKind::Tar) => list_tar(file)?,
+    Some(ArchiveKind::TarGz) => list_tar_gz(file)?,
+    None => return Err(format!("{} is not a supported archive", path.display())),
+  };
+
+  if listing.truncated {
+    tracing::warn!(
+      path = %path.display(),
+      "archive listing truncated at {} entries",
+      listing.entries.len()
+    );
+  }
+  Ok(listing)
+}
+
+#[tauri::command]
+pub async fn list_archive(path: PathBuf) -> Result<ArchiveListing, String> {
+  tauri::async_runtime::spawn_blocking(move || list_entries(&path))
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::{self, File};
+  use std::io::Write;
+
+  use flate2::write::GzEncoder;
+  use flate2::Compression;
+  use zip::write::FileOptions;
+  use zip::ZipWriter;
+
+  use super::{list_entries, MAX_ENTRIES};
+
+  fn tar_with_files(count: usize) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for index in 0..count {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(4);
+      header.set_mode(0o644);
+      header.set_cksum();
+      builder
+        .append_data(&mut header, format!("file-{}.txt", index), &b"data"[..])
+        .unwrap();
+    }
+    builder.into_inner().unwrap()
+  }
+
+  #[test]
+  fn lists_zip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("docs.zip");
+    let mut writer = ZipWriter::new(File::create(&path).unwrap());
+    writer.add_directory("docs/", FileOptions::default()).unwrap();
+    writer.start_file("docs/readme.txt", FileOptions::default()).unwrap();
+    writer.write_all(b"hello").unwrap();
+    writer.finish().unwrap();
+
+    let listing = list_entries(&path).unwrap();
+    assert!(!listing.truncated);
+    let names: Vec<&str> = listing.entries.iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, ["docs/", "docs/readme.txt"]);
+    assert!(listing.entries[0].is_dir);
+    assert_eq!(listing.entries[1].size, 5);
+  }
+
+  #[test]
+  fn flags_truncated_zip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("many.zip");
+    let mut writer = ZipWriter::new(File::create(&path).unwrap());
+    for index in 0..=MAX_ENTRIES {
+      writer
+        .start_file(format!("{}.txt", index), FileOptions::default())
+        .unwrap();
+    }
+    writer.finish().unwrap();
+
+    let listing = list_entries(&path).unwrap();
+    assert!(listing.truncated);
+    assert_eq!(listing.entries.len(), MAX_ENTRIES);
+  }
+
+  #[test]
+  fn rejects_oversized_zip_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crafted.zip");
+    // An end record claiming a 1 GiB central directory and nothing else.
+    let mut end = b"PK\x05\x06".to_vec();
+    end.extend_from_slice(&[0; 4]);
+    end.extend_from_slice(&100u16.to_le_bytes());
+    end.extend_from_slice(&100u16.to_le_bytes());
+    end.extend_from_slice(&(1u32 << 30).to_le_bytes());
+    end.extend_from_slice(&[0; 6]);
+    fs::write(&path, end).unwrap();
+
+    assert_eq!(
+      list_entries(&path).unwrap_err(),
+      "Zip archive directory is too large to list"
+    );
+  }
+
+  #[test]
+  fn lists_tar_and_tar_gz() {
+    let dir = tempfile::tempdir().unwrap();
+    let tar_path = dir.path().join("files.tar");
+    let gz_path = dir.path().join("files.tar.gz");
+    let tar = tar_with_files(3);
+    fs::write(&tar_path, &tar).unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&tar).unwrap();
+    fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+
+    for (path, compression) in [(tar_path, "none"), (gz_path, "gzip")] {
+      let listing = list_entries(&path).unwrap();
+      assert!(!listing.truncated);
+      assert_eq!(listing.entries.len(), 3);
+      assert_eq!(listing.entries[2].name, "file-2.txt");
+      assert_eq!(listing.entries[2].size, 4);
+      assert_eq!(listing.entries[2].compression, compression);
+    }
+  }
+
+  #[test]
+  fn flags_truncated_tar() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("many.tar");
+    fs::write(&path, tar_with_files(MAX_ENTRIES + 1)).unwrap();
+
+    let listing = list_entries(&path).unwrap();
+    assert!(listing.truncated);
+    assert_eq!(listing.entries.len(), MAX_ENTRIES);
+
+    fs::write(&path, tar_with_files(MAX_ENTRIES)).unwrap();
+    assert!(!list_entries(&path).unwrap().truncated);
+  }
+}
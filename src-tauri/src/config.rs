@@ -0,0 +1,342 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tracing::level_filters::LevelFilter;
+
+use crate::downloads::{self, DownloadDirSource};
+use crate::logging;
+use crate::settings::{Settings, SettingsStore};
+
+/// Directory received files are saved to.
+pub const DOWNLOAD_DIR_ENV: &str = "ARK_DROP_DOWNLOAD_DIR";
+/// Log level, e.g. `debug`.
+pub const LOG_LEVEL_ENV: &str = "ARK_DROP_LOG_LEVEL";
+
+/// Appended to a variable name to make its value win over the user's
+/// settings, e.g. `ARK_DROP_DOWNLOAD_DIR_FORCE`.
+const FORCE_SUFFIX: &str = "_FORCE";
+
+/// A value preset through the environment.
+#[derive(Debug, Clone)]
+pub struct Preset<T> {
+  pub value: T,
+  /// Set through the `_FORCE` variant, so it takes precedence over settings.
+  pub forced: bool,
+}
+
+/// Configuration read from `ARK_DROP_*` environment variables, used to
+/// preconfigure kiosk machines and CI runs. Plain variables only provide
+/// defaults that the user's settings override; `_FORCE` variants win.
+#[derive(Debug, Clone, Default)]
+pub struct EnvConfig {
+  pub download_dir: Option<Preset<PathBuf>>,
+  pub log_level: Option<Preset<LevelFilter>>,
+}
+
+impl EnvConfig {
+  pub fn from_env() -> (Self, Vec<String>) {
+    Self::from_lookup(|name| std::env::var_os(name))
+  }
+
+  /// Reads every variable through `lookup`. Malformed values are left out of
+  /// the config and returned alongside it, one message per variable.
+  pub fn from_lookup<F>(lookup: F) -> (Self, Vec<String>)
+  where
+    F: Fn(&str) -> Option<OsString>,
+  {
+    let mut errors = Vec::new();
+    let config = Self {
+      download_dir: read(&lookup, DOWNLOAD_DIR_ENV, parse_dir, &mut errors),
+      log_level: read(&lookup, LOG_LEVEL_ENV, logging::parse_level, &mut errors),
+    };
+    (config, errors)
+  }
+}
+
+fn read<T, F, P>(lookup: &F, name: &str, parse: P, errors: &mut Vec<String>) -> Option<Preset<T>>
+where
+  F: Fn(&str) -> Option<OsString>,
+  P: Fn(&str) -> Result<T, String>,
+{
+  let forced_name = format!("{}{}", name, FORCE_SUFFIX);
+  let (name, raw, forced) = match lookup(&forced_name) {
+    Some(raw) => (forced_name.as_str(), raw, true),
+    None => (name, lookup(name)?, false),
+  };
+
+  let parsed = raw
+    .to_str()
+    .ok_or_else(|| "not valid text".to_string())
+    .and_then(parse);
+  match parsed {
+    Ok(value) => Some(Preset { value, forced }),
+    Err(error) => {
+      errors.push(format!("{}: {}", name, error));
+      None
+    }
+  }
+}
+
+fn parse_dir(value: &str) -> Result<PathBuf, String> {
+  let path = downloads::expand_home(Path::new(value.trim()))?;
+  if path.is_absolute() {
+    Ok(path)
+  } else {
+    Err("must be an absolute path".to_string())
+  }
+}
+
+/// Where an effective configuration value comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+  Default,
+  Environment,
+  Settings,
+  Forced,
+}
+
+/// Picks between an environment preset and the user's setting: a forced
+/// preset wins, then the setting, then a plain preset.
+pub fn merge<T: Clone>(
+  preset: Option<&Preset<T>>,
+  setting: Option<T>,
+) -> Option<(T, ConfigSource)> {
+  match (preset, setting) {
+    (Some(preset), _) if preset.forced => Some((preset.value.clone(), ConfigSource::Forced)),
+    (_, Some(setting)) => Some((setting, ConfigSource::Settings)),
+    (Some(preset), None) => Some((preset.value.clone(), ConfigSource::Environment)),
+    (None, None) => None,
+  }
+}
+
+/// The level to log at given the persisted settings and the environment.
+pub fn effective_log_level(settings: &Settings, env: &EnvConfig) -> (LevelFilter, ConfigSource) {
+  let setting = settings
+    .log_level
+    .as_deref()
+    .and_then(|level| logging::parse_level(level).ok());
+  merge(env.log_level.as_ref(), setting).unwrap_or((LevelFilter::INFO, ConfigSource::Default))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValue<T> {
+  pub value: T,
+  pub source: ConfigSource,
+}
+
+/// The configuration actually in use, for debugging deployments.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveConfig {
+  pub download_dir: Option<ConfigValue<PathBuf>>,
+  pub log_level: ConfigValue<String>,
+}
+
+impl EffectiveConfig {
+  pub fn resolve(settings: &SettingsStore, env: &EnvConfig) -> Self {
+    let download_dir = downloads::effective_download_dir(settings, env).map(|(value, source)| {
+      let source = match source {
+        DownloadDirSource::Custom => ConfigSource::Settings,
+        DownloadDirSource::Environment => ConfigSource::Environment,
+        DownloadDirSource::Forced => ConfigSource::Forced,
+        DownloadDirSource::System | DownloadDirSource::Fallback => ConfigSource::Default,
+      };
+      ConfigValue { value, source }
+    });
+    let (level, source) = effective_log_level(&settings.get(), env);
+
+    Self {
+      download_dir,
+      log_level: ConfigValue {
+        value: level.to_string().to_lowercase(),
+        source,
+      },
+    }
+  }
+}
+
+#[tauri::command]
+pub fn get_effective_config(
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+) -> EffectiveConfig {
+  EffectiveConfig::resolve(&settings, &env)
+}
+
+/// Why `get_app_config` refused to return a value.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppConfigError {
+  /// The key is not one of the ARK Drop settings the frontend may read.
+  NotAllowed { key: String },
+  Unavailable { key: String, message: String },
+}
+
+/// Reads one effective configuration value. Only the keys matched below are
+/// served, so the webview can't use this to probe arbitrary environment
+/// variables.
+pub fn app_config(
+  key: String,
+  settings: &SettingsStore,
+  env: &EnvConfig,
+) -> Result<serde_json::Value, AppConfigError> {
+  let config = EffectiveConfig::resolve(settings, env);
+  let value = match key.as_str() {
+    "download_dir" => serde_json::to_value(config.download_dir),
+    "log_level" => serde_json::to_value(config.log_level),
+    _ => {
+      tracing::warn!(%key, "refused to serve a config key outside the allowlist");
+      return Err(AppConfigError::NotAllowed { key });
+    }
+  };
+
+  value.map_err(|error| AppConfigError::Unavailable {
+    key,
+    message: error.to_string(),
+  })
+}
+
+#[tauri::command]
+pub fn get_app_config(
+  key: String,
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+) -> Result<serde_json::Value, AppConfigError> {
+  app_config(key, &settings, &env)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use std::ffi::OsString;
+
+  use tracing::level_filters::LevelFilter;
+
+  use super::{
+    app_config, merge, AppConfigError, ConfigSource, EnvConfig, Preset, DOWNLOAD_DIR_ENV,
+    LOG_LEVEL_ENV,
+  };
+  use crate::settings::SettingsStore;
+
+  fn env_from(vars: &[(&str, &str)]) -> (EnvConfig, Vec<String>) {
+    let vars: HashMap<String, OsString> = vars
+      .iter()
+      .map(|(name, value)| (name.to_string(), OsString::from(value)))
+      .collect();
+    EnvConfig::from_lookup(|name| vars.get(name).cloned())
+  }
+
+  #[test]
+  fn merge_precedence() {
+    let plain = Preset {
+      value: "env",
+      forced: false,
+    };
+    let forced = Preset {
+      value: "env",
+      forced: true,
+    };
+    let cases = [
+      (None, None, None),
+      (None, Some("user"), Some(("user", ConfigSource::Settings))),
+      (Some(&plain), None, Some(("env", ConfigSource::Environment))),
+      (Some(&plain), Some("user"), Some(("user", ConfigSource::Settings))),
+      (Some(&forced), None, Some(("env", ConfigSource::Forced))),
+      (Some(&forced), Some("user"), Some(("env", ConfigSource::Forced))),
+    ];
+
+    for (preset, setting, expected) in cases {
+      assert_eq!(merge(preset, setting), expected, "{:?} / {:?}", preset, setting);
+    }
+  }
+
+  #[test]
+  fn reads_presets() {
+    let dir = std::env::temp_dir();
+    let force_name = format!("{}_FORCE", LOG_LEVEL_ENV);
+    let cases = [
+      (vec![], None, None),
+      (vec![(LOG_LEVEL_ENV, " DEBUG ")], Some((LevelFilter::DEBUG, false)), None),
+      (vec![(force_name.as_str(), "warn")], Some((LevelFilter::WARN, true)), None),
+      (
+        vec![(LOG_LEVEL_ENV, "debug"), (force_name.as_str(), "error")],
+        Some((LevelFilter::ERROR, true)),
+        None,
+      ),
+      (
+        vec![(DOWNLOAD_DIR_ENV, dir.to_str().unwrap())],
+        None,
+        Some((dir.clone(), false)),
+      ),
+    ];
+
+    for (vars, level, download_dir) in cases {
+      let (config, errors) = env_from(&vars);
+      assert!(errors.is_empty(), "{:?}", errors);
+      let read_level = config.log_level.map(|preset| (preset.value, preset.forced));
+      let read_dir = config.download_dir.map(|preset| (preset.value, preset.forced));
+      assert_eq!(read_level, level, "{:?}", vars);
+      assert_eq!(read_dir, download_dir, "{:?}", vars);
+    }
+  }
+
+  #[test]
+  fn forced_value_is_used_even_if_plain_one_is_invalid() {
+    let force_name = format!("{}_FORCE", LOG_LEVEL_ENV);
+    let (config, errors) = env_from(&[(LOG_LEVEL_ENV, "loud"), (&force_name, "info")]);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(config.log_level.unwrap().value, LevelFilter::INFO);
+  }
+
+  #[test]
+  fn reports_and_ignores_every_invalid_value() {
+    let force_name = format!("{}_FORCE", DOWNLOAD_DIR_ENV);
+    let (config, errors) = env_from(&[(LOG_LEVEL_ENV, "loud"), (&force_name, "transfers")]);
+    assert!(config.log_level.is_none());
+    assert!(config.download_dir.is_none());
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(
+      errors[0].starts_with("ARK_DROP_DOWNLOAD_DIR_FORCE: must be an absolute path"),
+      "{:?}",
+      errors
+    );
+    assert!(
+      errors[1].starts_with("ARK_DROP_LOG_LEVEL: Unknown log level 'loud'"),
+      "{:?}",
+      errors
+    );
+  }
+
+  #[test]
+  fn rejects_relative_download_dir() {
+    for value in ["transfers", "./transfers", "../transfers"] {
+      let (config, errors) = env_from(&[(DOWNLOAD_DIR_ENV, value)]);
+      assert!(config.download_dir.is_none());
+      assert_eq!(errors, ["ARK_DROP_DOWNLOAD_DIR: must be an absolute path"]);
+    }
+  }
+
+  #[test]
+  fn serves_only_allowlisted_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let settings = SettingsStore::load(dir.path());
+    let (env, _) = env_from(&[(DOWNLOAD_DIR_ENV, dir.path().to_str().unwrap())]);
+
+    for key in ["AWS_SECRET_ACCESS_KEY", "SSH_AUTH_SOCK", "PATH", "", DOWNLOAD_DIR_ENV] {
+      match app_config(key.to_string(), &settings, &env) {
+        Err(AppConfigError::NotAllowed { key: refused }) => assert_eq!(refused, key),
+        other => panic!("{:?} was served: {:?}", key, other),
+      }
+    }
+
+    let download_dir = app_config("download_dir".to_string(), &settings, &env).unwrap();
+    assert_eq!(download_dir["value"], dir.path().to_str().unwrap());
+    assert_eq!(download_dir["source"], "environment");
+    let log_level = app_config("log_level".to_string(), &settings, &env).unwrap();
+    assert_eq!(log_level["value"], "info");
+    assert_eq!(log_level["source"], "default");
+  }
+}
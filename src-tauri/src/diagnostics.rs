@@ -0,0 +1,427 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::logging::{self, Logging};
+
+/// How much of a log file is read into memory at a time when tailing it.
+const READ_BLOCK_BYTES: u64 = 64 * 1024;
+
+/// One parsed line of the log file, as shown in the diagnostics view.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+  pub timestamp: String,
+  pub level: String,
+  pub target: String,
+  pub message: String,
+}
+
+impl LogEntry {
+  /// Parses a line written by the fmt layer, e.g.
+  /// `2024-06-01T10:00:00.000000Z  INFO app::logging: log level changed`.
+  fn parse(line: &str) -> Option<(Self, Level)> {
+    let mut parts = line.trim_start().splitn(2, char::is_whitespace);
+    let timestamp = parts.next()?;
+    let rest = parts.next()?.trim_start();
+    let mut parts = rest.splitn(2, char::is_whitespace);
+    let level: Level = parts.next()?.parse().ok()?;
+    let (target, message) = parts.next()?.trim_start().split_once(": ")?;
+
+    let entry = Self {
+      timestamp: timestamp.to_string(),
+      level: level.to_string(),
+      target: target.to_string(),
+      message: message.to_string(),
+    };
+    Some((entry, level))
+  }
+}
+
+/// Calls `visit` with each complete line of `path`, last line first, until it
+/// returns `false`. Only one block is held in memory at a time, and a final
+/// line that is still being written (no trailing newline yet) is skipped.
+fn for_each_line_rev<F>(path: &Path, mut visit: F) -> io::Result<()>
+where
+  F: FnMut(&str) -> bool,
+{
+  let mut file = match File::open(path) {
+    Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+    file => file?,
+  };
+
+  let mut end = file.metadata()?.len();
+  let mut pending = Vec::new();
+  let mut skip_partial = true;
+
+  while end > 0 {
+    let start = end.saturating_sub(READ_BLOCK_BYTES);
+    let mut block = vec![0; (end - start) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut block)?;
+    block.append(&mut pending);
+    end = start;
+
+    if skip_partial {
+      match block.iter().rposition(|&byte| byte == b'\n') {
+        Some(index) => block.truncate(index + 1),
+        None => continue,
+      }
+      skip_partial = false;
+    }
+
+    // `block` now ends right after a newline; peel lines off the back.
+    let mut line_end = block.len();
+    while line_end > 0 {
+      let content_end = line_end - 1;
+      match block[..content_end].iter().rposition(|&byte| byte == b'\n') {
+        Some(index) => {
+          if !visit(&String::from_utf8_lossy(&block[index + 1..content_end])) {
+            return Ok(());
+          }
+          line_end = index + 1;
+        }
+        None if end == 0 => {
+          visit(&String::from_utf8_lossy(&block[..content_end]));
+          return Ok(());
+        }
+        None => {
+          block.truncate(line_end);
+          pending = block;
+          break;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Collects the last `count` entries at `min_level` or more severe, oldest
+/// first, continuing into rotated files when the current one is too short.
+/// Lines that don't start a new entry (multi-line messages) are appended to
+/// the entry they belong to.
+pub fn recent_entries(
+  files: &[PathBuf],
+  count: usize,
+  min_level: LevelFilter,
+) -> io::Result<Vec<LogEntry>> {
+  let mut entries = Vec::new();
+  let mut continuation: Vec<String> = Vec::new();
+
+  for path in files {
+    if entries.len() >= count {
+      break;
+    }
+    for_each_line_rev(path, |line| {
+      match LogEntry::parse(line) {
+        Some((mut entry, level)) => {
+          if level <= min_level {
+            for extra in continuation.iter().rev() {
+              entry.message.push('\n');
+              entry.message.push_str(extra);
+            }
+            entries.push(entry);
+          }
+          continuation.clear();
+        }
+        None => continuation.push(line.to_string()),
+      }
+      entries.len() < count
+    })?;
+  }
+
+  entries.reverse();
+  Ok(entries)
+}
+
+/// Writes every file in the log directory into a new zip archive at `dest`.
+/// An existing file at `dest` is never overwritten.
+pub fn export_log_archive(dir: &Path, dest: &Path) -> Result<(), String> {
+  let file = OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(dest)
+    .map_err(|error| match error.kind() {
+      io::ErrorKind::AlreadyExists => format!("{} already exists", dest.display()),
+      _ => error.to_string(),
+    })?;
+  // Never zip the archive into itself, however the caller got it into `dir`.
+  let dest = dest.canonicalize().map_err(|error| error.to_string())?;
+  let mut archive = ZipWriter::new(file);
+  let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+  for entry in fs::read_dir(dir).map_err(|error| error.to_string())? {
+    let path = entry.map_err(|error| error.to_string())?.path();
+    if path.canonicalize().ok().as_ref() == Some(&dest) {
+      continue;
+    }
+    let name = match path.file_name() {
+      Some(name) if path.is_file() => name.to_string_lossy().into_owned(),
+      _ => continue,
+    };
+    archive.start_file(name, options).map_err(|error| error.to_string())?;
+    let mut source = File::open(&path).map_err(|error| error.to_string())?;
+    io::copy(&mut source, &mut archive).map_err(|error| error.to_string())?;
+  }
+
+  archive.finish().map_err(|error| error.to_string())?;
+  Ok(())
+}
+
+/// Checks where the user asked to export the logs to, returning the path with
+/// its parent directory resolved. The destination must be an absolute path to
+/// a `.zip` file outside `log_dir`, also once links and `..` are resolved.
+pub fn export_destination(dest: &Path, log_dir: &Path) -> Result<PathBuf, String> {
+  if !dest.is_absolute() {
+    return Err(format!("{} is not an absolute path", dest.display()));
+  }
+  let is_zip = matches!(
+    dest.extension(),
+    Some(extension) if extension.eq_ignore_ascii_case("zip")
+  );
+  let (parent, name) = match (dest.parent(), dest.file_name()) {
+    (Some(parent), Some(name)) if is_zip => (parent, name),
+    _ => return Err("Logs can only be exported to a .zip file".to_string()),
+  };
+
+  let parent = parent
+    .canonicalize()
+    .map_err(|error| format!("Cannot export to {}: {}", parent.display(), error))?;
+  let log_dir = log_dir.canonicalize().map_err(|error| error.to_string())?;
+  if parent.starts_with(&log_dir) {
+    return Err("Logs cannot be exported into the log directory itself".to_string());
+  }
+  Ok(parent.join(name))
+}
+
+#[tauri::command]
+pub async fn get_recent_logs(
+  lines: usize,
+  min_level: Option<String>,
+  logging: tauri::State<'_, Logging>,
+) -> Result<Vec<LogEntry>, String> {
+  let min_level = match min_level {
+    Some(level) => logging::parse_level(&level)?,
+    None => LevelFilter::TRACE,
+  };
+  let files = logging.file()?.files();
+  tauri::async_runtime::spawn_blocking(move || recent_entries(&files, lines, min_level))
+    .await
+    .map_err(|error| error.to_string())?
+    .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub async fn export_logs(
+  dest_path: PathBuf,
+  logging: tauri::State<'_, Logging>,
+) -> Result<(), String> {
+  let dir = logging.file()?.dir().to_path_buf();
+  tauri::async_runtime::spawn_blocking(move || {
+    let dest = export_destination(&dest_path, &dir)?;
+    export_log_archive(&dir, &dest)
+  })
+  .await
+  .map_err(|error| error.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::Path;
+
+  use tracing::level_filters::LevelFilter;
+
+  use super::{
+    export_destination, export_log_archive, for_each_line_rev, recent_entries, READ_BLOCK_BYTES,
+  };
+
+  fn log_line(index: usize, level: &str) -> String {
+    format!(
+      "2024-06-01T10:00:00.{:06}Z {:>5} app::test: message {}",
+      index, level, index
+    )
+  }
+
+  fn lines_rev(path: &Path) -> Vec<String> {
+    let mut lines = Vec::new();
+    for_each_line_rev(path, |line| {
+      lines.push(line.to_string());
+      true
+    })
+    .unwrap();
+    lines
+  }
+
+  #[test]
+  fn reads_multi_megabyte_log_across_blocks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ark-drop.log");
+    // Varying lengths make lines straddle block boundaries at many offsets,
+    // and one line is longer than a whole block.
+    let mut lines: Vec<String> = (0..40_000)
+      .map(|index| format!("{}{}", log_line(index, "INFO"), "x".repeat(index % 97)))
+      .collect();
+    lines.insert(20_000, "y".repeat(3 * READ_BLOCK_BYTES as usize));
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    assert!(contents.len() > 4 * 1024 * 1024);
+    fs::write(&path, contents).unwrap();
+
+    let mut read = lines_rev(&path);
+    read.reverse();
+    assert_eq!(read, lines);
+  }
+
+  #[test]
+  fn line_ending_on_a_block_boundary() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ark-drop.log");
+    let first = "a".repeat(READ_BLOCK_BYTES as usize - 1);
+    let second = "b".repeat(READ_BLOCK_BYTES as usize - 1);
+    fs::write(&path, format!("{}\n{}\n", first, second)).unwrap();
+
+    assert_eq!(lines_rev(&path), vec![second, first]);
+  }
+
+  #[test]
+  fn skips_trailing_partial_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ark-drop.log");
+    fs::write(
+      &path,
+      format!("{}\n{}\n{}", log_line(1, "INFO"), log_line(2, "WARN"), "2024-06-01T10:00"),
+    )
+    .unwrap();
+    assert_eq!(lines_rev(&path), vec![log_line(2, "WARN"), log_line(1, "INFO")]);
+
+    // The writer finishing the line makes it visible on the next read.
+    let mut contents = fs::read_to_string(&path).unwrap();
+    contents.truncate(contents.rfind('\n').unwrap() + 1);
+    contents.push_str(&log_line(3, "ERROR"));
+    fs::write(&path, &contents).unwrap();
+    assert_eq!(lines_rev(&path).len(), 2);
+    contents.push('\n');
+    fs::write(&path, &contents).unwrap();
+    assert_eq!(lines_rev(&path)[0], log_line(3, "ERROR"));
+  }
+
+  #[test]
+  fn partial_line_only_yields_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ark-drop.log");
+    fs::write(&path, "x".repeat(2 * READ_BLOCK_BYTES as usize)).unwrap();
+    assert!(lines_rev(&path).is_empty());
+    assert!(lines_rev(&dir.path().join("missing.log")).is_empty());
+  }
+
+  #[test]
+  fn joins_continuation_lines_across_rotated_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let current = dir.path().join("ark-drop.log");
+    let rotated = dir.path().join("ark-drop.log.1");
+    fs::write(
+      &rotated,
+      format!("{}\n{}\n", log_line(1, "INFO"), log_line(2, "ERROR")),
+    )
+    .unwrap();
+    fs::write(
+      &current,
+      format!("first detail\nsecond detail\n{}\n", log_line(3, "INFO")),
+    )
+    .unwrap();
+
+    let entries = recent_entries(&[current, rotated], 2, LevelFilter::TRACE).unwrap();
+    let messages: Vec<&str> = entries.iter().map(|entry| entry.message.as_str()).collect();
+    assert_eq!(messages, ["message 2\nfirst detail\nsecond detail", "message 3"]);
+    assert_eq!(entries[0].level, "ERROR");
+  }
+
+  #[test]
+  fn filters_by_level_and_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ark-drop.log");
+    let lines: Vec<String> = (0..10)
+      .map(|index| log_line(index, if index % 2 == 0 { "DEBUG" } else { "WARN" }))
+      .collect();
+    fs::write(&path, format!("{}\n", lines.join("\n"))).unwrap();
+    let files = [path];
+
+    let warnings = recent_entries(&files, 3, LevelFilter::WARN).unwrap();
+    let messages: Vec<&str> = warnings.iter().map(|entry| entry.message.as_str()).collect();
+    assert_eq!(messages, ["message 5", "message 7", "message 9"]);
+    assert!(recent_entries(&files, 0, LevelFilter::TRACE).unwrap().is_empty());
+  }
+
+  #[test]
+  fn export_never_overwrites() {
+    let logs = tempfile::tempdir().unwrap();
+    fs::write(logs.path().join("ark-drop.log"), "line\n").unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let dest = out.path().join("logs.zip");
+    fs::write(&dest, "keep me").unwrap();
+
+    assert!(export_log_archive(logs.path(), &dest).is_err());
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "keep me");
+
+    let fresh = out.path().join("fresh.zip");
+    export_log_archive(logs.path(), &fresh).unwrap();
+    let archive = zip::ZipArchive::new(fs::File::open(fresh).unwrap()).unwrap();
+    assert_eq!(archive.file_names().collect::<Vec<_>>(), ["ark-drop.log"]);
+  }
+
+  #[test]
+  fn export_destination_must_be_outside_the_log_directory() {
+    let root = tempfile::tempdir().unwrap();
+    let logs = root.path().join("logs");
+    fs::create_dir(&logs).unwrap();
+
+    let outside = root.path().join("logs.zip");
+    assert_eq!(
+      export_destination(&outside, &logs),
+      Ok(root.path().canonicalize().unwrap().join("logs.zip"))
+    );
+    assert!(export_destination(Path::new("logs.zip"), &logs)
+      .unwrap_err()
+      .contains("not an absolute path"));
+    assert!(export_destination(&root.path().join("logs.txt"), &logs).is_err());
+
+    let sneaky = logs.join("..").join("logs").join("export.zip");
+    assert_eq!(
+      export_destination(&sneaky, &logs),
+      Err("Logs cannot be exported into the log directory itself".to_string())
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn export_destination_resolves_linked_parents() {
+    let root = tempfile::tempdir().unwrap();
+    let logs = root.path().join("logs");
+    fs::create_dir(&logs).unwrap();
+    let link = root.path().join("link");
+    std::os::unix::fs::symlink(&logs, &link).unwrap();
+
+    assert_eq!(
+      export_destination(&link.join("export.zip"), &logs),
+      Err("Logs cannot be exported into the log directory itself".to_string())
+    );
+  }
+
+  #[test]
+  fn export_skips_the_archive_itself() {
+    let logs = tempfile::tempdir().unwrap();
+    fs::write(logs.path().join("ark-drop.log"), "line\n").unwrap();
+    let dest = logs.path().join("export.zip");
+
+    export_log_archive(logs.path(), &dest).unwrap();
+    let archive = zip::ZipArchive::new(fs::File::open(dest).unwrap()).unwrap();
+    assert_eq!(archive.file_names().collect::<Vec<_>>(), ["ark-drop.log"]);
+  }
+}
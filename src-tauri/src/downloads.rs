@@ -0,0 +1,526 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::{self, ConfigSource, EnvConfig};
+use crate::settings::SettingsStore;
+
+/// How many probe file names [`probe_writable`] tries before giving up.
+const MAX_PROBE_ATTEMPTS: u32 = 16;
+
+const NO_DOWNLOAD_DIR: &str = "No download directory is available";
+
+/// Expands a leading `~` to the user's home directory. Other paths are
+/// returned unchanged.
+pub fn expand_home(path: &Path) -> Result<PathBuf, String> {
+  let mut components = path.components();
+  match components.next() {
+    Some(Component::Normal(first)) if first == OsStr::new("~") => {
+      let home = tauri::api::path::home_dir()
+        .ok_or_else(|| "Could not determine the home directory".to_string())?;
+      Ok(home.join(components.as_path()))
+    }
+    _ => Ok(path.to_path_buf()),
+  }
+}
+
+/// Why a directory can't be used for received files.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DownloadDirError {
+  /// Relative input, which would depend on where the app was started from.
+  NotAbsolute { path: PathBuf },
+  Missing { path: PathBuf },
+  NotADirectory { path: PathBuf },
+  ReadOnly { path: PathBuf },
+  PermissionDenied { path: PathBuf },
+  Other { path: PathBuf, message: String },
+}
+
+impl fmt::Display for DownloadDirError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::NotAbsolute { path } => write!(f, "{} is not an absolute path", path.display()),
+      Self::Missing { path } => write!(f, "{} does not exist", path.display()),
+      Self::NotADirectory { path } => write!(f, "{} is not a directory", path.display()),
+      Self::ReadOnly { path } => write!(f, "{} is read-only", path.display()),
+      Self::PermissionDenied { path } => {
+        write!(f, "Permission denied writing to {}", path.display())
+      }
+      Self::Other { path, message } => write!(f, "{} cannot be used: {}", path.display(), message),
+    }
+  }
+}
+
+impl DownloadDirError {
+  fn from_io(path: &Path, error: io::Error) -> Self {
+    let path = path.to_path_buf();
+    match error.kind() {
+      io::ErrorKind::NotFound => Self::Missing { path },
+      _ if is_read_only_fs(&error) => Self::ReadOnly { path },
+      io::ErrorKind::PermissionDenied if is_read_only_dir(&path) => Self::ReadOnly { path },
+      io::ErrorKind::PermissionDenied => Self::PermissionDenied { path },
+      _ => Self::Other {
+        path,
+        message: error.to_string(),
+      },
+    }
+  }
+}
+
+#[cfg(unix)]
+fn is_read_only_fs(error: &io::Error) -> bool {
+  // EROFS has the same value on Linux and macOS.
+  error.raw_os_error() == Some(30)
+}
+
+#[cfg(not(unix))]
+fn is_read_only_fs(_error: &io::Error) -> bool {
+  false
+}
+
+/// Whether no one has write permission on `path`.
+#[cfg(unix)]
+fn is_read_only_dir(path: &Path) -> bool {
+  fs::metadata(path)
+    .map(|metadata| metadata.permissions().readonly())
+    .unwrap_or(false)
+}
+
+/// Windows ignores the read-only attribute on directories, so a denied write
+/// there is always reported as `PermissionDenied`.
+#[cfg(not(unix))]
+fn is_read_only_dir(_path: &Path) -> bool {
+  false
+}
+
+/// Turns user input such as `~/Downloads` or `/mnt/usb/../transfers` into a
+/// canonical path to a directory that files can be written to, creating it
+/// first if asked to. Relative input is rejected.
+pub fn resolve_directory(
+  input: &str,
+  create_if_missing: bool,
+) -> Result<PathBuf, DownloadDirError> {
+  let requested = Path::new(input.trim());
+  let expanded = expand_home(requested).map_err(|message| DownloadDirError::Other {
+    path: requested.to_path_buf(),
+    message,
+  })?;
+  if !expanded.is_absolute() {
+    return Err(DownloadDirError::NotAbsolute {
+      path: requested.to_path_buf(),
+    });
+  }
+
+  if create_if_missing && !expanded.exists() {
+    fs::create_dir_all(&expanded).map_err(|error| DownloadDirError::from_io(&expanded, error))?;
+  }
+
+  let resolved =
+    fs::canonicalize(&expanded).map_err(|error| DownloadDirError::from_io(&expanded, error))?;
+  if !resolved.is_dir() {
+    return Err(DownloadDirError::NotADirectory { path: resolved });
+  }
+
+  probe_writable(&resolved).map_err(|error| DownloadDirError::from_io(&resolved, error))?;
+  Ok(resolved)
+}
+
+/// Where the effective download directory comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadDirSource {
+  /// Chosen by the user in the settings.
+  Custom,
+  /// The downloads folder reported by the operating system.
+  System,
+  /// `~/Downloads`, used when the platform doesn't report a downloads folder.
+  Fallback,
+  /// Preset through `ARK_DROP_DOWNLOAD_DIR` and not overridden by the user.
+  Environment,
+  /// Set through `ARK_DROP_DOWNLOAD_DIR_FORCE`, ignoring the user's choice.
+  Forced,
+}
+
+/// Download directory details shown on the settings screen.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadDirInfo {
+  pub effective: PathBuf,
+  pub source: DownloadDirSource,
+  pub system_default: Option<PathBuf>,
+  pub writable: bool,
+}
+
+/// The default directory when the user hasn't picked one.
+pub fn default_download_dir() -> Option<(PathBuf, DownloadDirSource)> {
+  tauri::api::path::download_dir()
+    .map(|dir| (dir, DownloadDirSource::System))
+    .or_else(|| {
+      tauri::api::path::home_dir()
+        .map(|home| (home.join("Downloads"), DownloadDirSource::Fallback))
+    })
+}
+
+/// The directory received files go to and where that choice came from.
+pub fn effective_download_dir(
+  settings: &SettingsStore,
+  env: &EnvConfig,
+) -> Option<(PathBuf, DownloadDirSource)> {
+  match config::merge(env.download_dir.as_ref(), settings.get().download_dir) {
+    Some((dir, ConfigSource::Forced)) => Some((dir, DownloadDirSource::Forced)),
+    Some((dir, ConfigSource::Environment)) => Some((dir, DownloadDirSource::Environment)),
+    Some((dir, _)) => Some((dir, DownloadDirSource::Custom)),
+    None => default_download_dir(),
+  }
+}
+
+/// Checks that files can be created in `dir` by actually creating (and
+/// removing) an empty one.
+pub fn probe_writable(dir: &Path) -> io::Result<()> {
+  let mut attempt = 0;
+  loop {
+    let probe = dir.join(format!(".ark-drop-write-test-{}-{}", std::process::id(), attempt));
+    let created = OpenOptions::new()
+      .write(true)
+      .create_new(true)
+      .open(&probe)
+      .map(drop);
+    match created {
+      Ok(()) => return fs::remove_file(&probe),
+      // Left behind by a run that didn't get to clean up, or another probe of
+      // the same directory in progress.
+      Err(error)
+        if error.kind() == io::ErrorKind::AlreadyExists && attempt < MAX_PROBE_ATTEMPTS =>
+      {
+        attempt += 1;
+      }
+      Err(error) => return Err(error),
+    }
+  }
+}
+
+#[tauri::command]
+pub fn get_download_directory(
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+) -> Result<PathBuf, String> {
+  effective_download_dir(&settings, &env)
+    .map(|(dir, _)| dir)
+    .ok_or_else(|| NO_DOWNLOAD_DIR.to_string())
+}
+
+pub fn download_dir_info(
+  settings: &SettingsStore,
+  env: &EnvConfig,
+) -> Result<DownloadDirInfo, String> {
+  let (effective, source) =
+    effective_download_dir(settings, env).ok_or_else(|| NO_DOWNLOAD_DIR.to_string())?;
+  Ok(describe_download_dir(effective, source))
+}
+
+/// Fills in the details of `effective`, probing it for write access.
+fn describe_download_dir(effective: PathBuf, source: DownloadDirSource) -> DownloadDirInfo {
+  let writable = probe_writable(&effective).is_ok();
+  DownloadDirInfo {
+    effective,
+    source,
+    system_default: default_download_dir().map(|(dir, _)| dir),
+    writable,
+  }
+}
+
+#[tauri::command]
+pub async fn get_download_dir_info(
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+) -> Result<DownloadDirInfo, String> {
+  let (effective, source) =
+    effective_download_dir(&settings, &env).ok_or_else(|| NO_DOWNLOAD_DIR.to_string())?;
+  // The directory may be on a slow or unresponsive network drive.
+  tauri::async_runtime::spawn_blocking(move || describe_download_dir(effective, source))
+    .await
+    .map_err(|error| error.to_string())
+}
+
+/// Forgets the user's choice so received files go to the system default
+/// again.
+#[tauri::command]
+pub fn clear_download_directory(settings: tauri::State<'_, SettingsStore>) -> Result<(), String> {
+  settings.update(|current| current.download_dir = None)?;
+  tracing::info!("download directory reset to the system default");
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn set_download_directory(
+  path: String,
+  create_if_missing: bool,
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+) -> Result<PathBuf, DownloadDirError> {
+  if let Some(preset) = env.download_dir.as_ref().filter(|preset| preset.forced) {
+    return Err(DownloadDirError::Other {
+      path: preset.value.clone(),
+      message: format!("the download directory is fixed by {}_FORCE", config::DOWNLOAD_DIR_ENV),
+    });
+  }
+
+  let requested = PathBuf::from(path.trim());
+  let resolved = tauri::async_runtime::spawn_blocking(move || {
+    resolve_directory(&path, create_if_missing)
+  })
+  .await
+  .map_err(|error| DownloadDirError::Other {
+    path: requested,
+    message: error.to_string(),
+  })??;
+  settings
+    .update(|current| current.download_dir = Some(resolved.clone()))
+    .map_err(|message| DownloadDirError::Other {
+      path: resolved.clone(),
+      message,
+    })?;
+  tracing::info!(path = %resolved.display(), "download directory changed");
+  Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::{Path, PathBuf};
+
+  use super::{
+    default_download_dir, download_dir_info, expand_home, probe_writable, resolve_directory,
+    DownloadDirError, DownloadDirSource,
+  };
+  use crate::config::{EnvConfig, Preset};
+  use crate::settings::SettingsStore;
+
+  fn preset(dir: &Path, forced: bool) -> EnvConfig {
+    EnvConfig {
+      download_dir: Some(Preset {
+        value: dir.to_path_buf(),
+        forced,
+      }),
+      log_level: None,
+    }
+  }
+
+  fn store_with_dir(config_dir: &Path, download_dir: Option<PathBuf>) -> SettingsStore {
+    let store = SettingsStore::load(config_dir);
+    store
+      .update(|current| current.download_dir = download_dir)
+      .unwrap();
+    store
+  }
+
+  #[test]
+  fn info_reports_each_source() {
+    let config = tempfile::tempdir().unwrap();
+    let chosen = tempfile::tempdir().unwrap();
+    let preset_dir = tempfile::tempdir().unwrap();
+    let chosen = chosen.path();
+    let preset_dir = preset_dir.path();
+
+    let cases = [
+      (None, preset(preset_dir, false), preset_dir, DownloadDirSource::Environment),
+      (Some(chosen), preset(preset_dir, false), chosen, DownloadDirSource::Custom),
+      (Some(chosen), EnvConfig::default(), chosen, DownloadDirSource::Custom),
+      (Some(chosen), preset(preset_dir, true), preset_dir, DownloadDirSource::Forced),
+      (None, preset(preset_dir, true), preset_dir, DownloadDirSource::Forced),
+    ];
+    for (setting, env, effective, source) in cases {
+      let store = store_with_dir(config.path(), setting.map(Path::to_path_buf));
+      let info = download_dir_info(&store, &env).unwrap();
+      assert_eq!(info.effective, effective, "{:?} / {:?}", setting, env);
+      assert_eq!(info.source, source, "{:?} / {:?}", setting, env);
+      assert!(info.writable);
+    }
+  }
+
+  #[test]
+  fn info_falls_back_to_the_system_default() {
+    let config = tempfile::tempdir().unwrap();
+    let store = store_with_dir(config.path(), None);
+
+    let info = download_dir_info(&store, &EnvConfig::default());
+    match default_download_dir() {
+      Some((dir, source)) => {
+        let info = info.unwrap();
+        assert!(matches!(
+          info.source,
+          DownloadDirSource::System | DownloadDirSource::Fallback
+        ));
+        assert_eq!(info.source, source);
+        assert_eq!(info.effective, dir);
+        assert_eq!(info.system_default, Some(dir));
+      }
+      None => assert!(info.is_err()),
+    }
+  }
+
+  #[test]
+  fn info_reports_unwritable_directory() {
+    let config = tempfile::tempdir().unwrap();
+    let missing = config.path().join("unplugged-drive");
+    let store = store_with_dir(config.path(), Some(missing.clone()));
+
+    let info = download_dir_info(&store, &EnvConfig::default()).unwrap();
+    assert_eq!(info.effective, missing);
+    assert_eq!(info.source, DownloadDirSource::Custom);
+    assert!(!info.writable);
+  }
+
+  #[test]
+  fn missing_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing");
+
+    match resolve_directory(missing.to_str().unwrap(), false) {
+      Err(DownloadDirError::Missing { path }) => assert_eq!(path, missing),
+      other => panic!("unexpected {:?}", other),
+    }
+    assert!(!missing.exists());
+  }
+
+  #[test]
+  fn creates_missing_directory_when_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("new/nested");
+
+    let resolved = resolve_directory(missing.to_str().unwrap(), true).unwrap();
+    assert!(resolved.is_dir());
+    assert_eq!(resolved, missing.canonicalize().unwrap());
+  }
+
+  #[test]
+  fn file_is_not_a_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("notes.txt");
+    fs::write(&file, "notes").unwrap();
+
+    match resolve_directory(file.to_str().unwrap(), true) {
+      Err(DownloadDirError::NotADirectory { path }) => {
+        assert_eq!(path, file.canonicalize().unwrap())
+      }
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+
+  #[test]
+  fn leftover_probe_file_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let leftover = dir
+      .path()
+      .join(format!(".ark-drop-write-test-{}-0", std::process::id()));
+    fs::write(&leftover, "").unwrap();
+
+    probe_writable(dir.path()).unwrap();
+    assert!(leftover.exists());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+  }
+
+  /// Resolves `dir` after setting its mode, or returns `None` when the mode is
+  /// not enforced because the tests run as root.
+  #[cfg(unix)]
+  fn resolve_with_mode(dir: &std::path::Path, mode: u32) -> Option<DownloadDirError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(dir, fs::Permissions::from_mode(mode)).unwrap();
+    let result = resolve_directory(dir.to_str().unwrap(), false);
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+    result.err()
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn read_only_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    match resolve_with_mode(dir.path(), 0o555) {
+      None | Some(DownloadDirError::ReadOnly { .. }) => {}
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn permission_denied_directory() {
+    // Others may write, but the owner may not.
+    let dir = tempfile::tempdir().unwrap();
+    match resolve_with_mode(dir.path(), 0o577) {
+      None | Some(DownloadDirError::PermissionDenied { .. }) => {}
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+
+  fn home() -> PathBuf {
+    tauri::api::path::home_dir().unwrap()
+  }
+
+  #[test]
+  fn expands_only_a_leading_tilde() {
+    assert_eq!(expand_home(Path::new("~")).unwrap(), home());
+    assert_eq!(
+      expand_home(Path::new("~/Downloads/ark")).unwrap(),
+      home().join("Downloads/ark")
+    );
+    for unchanged in ["~other/Downloads", "Downloads/~", "transfers", "./~"] {
+      assert_eq!(expand_home(Path::new(unchanged)).unwrap(), Path::new(unchanged));
+    }
+  }
+
+  #[test]
+  fn resolves_tilde_input() {
+    let dir = tempfile::Builder::new()
+      .prefix(".ark-drop-test-")
+      .tempdir_in(home())
+      .unwrap();
+    let name = dir.path().file_name().unwrap().to_str().unwrap();
+    fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+    let resolved = resolve_directory(&format!(" ~/{}/ ", name), false).unwrap();
+    assert_eq!(resolved, dir.path().canonicalize().unwrap());
+
+    let created = resolve_directory(&format!("~/{}/received", name), true).unwrap();
+    assert_eq!(created, dir.path().canonicalize().unwrap().join("received"));
+
+    match resolve_directory(&format!("~/{}/notes.txt", name), true) {
+      Err(DownloadDirError::NotADirectory { path }) => {
+        assert_eq!(path, dir.path().canonicalize().unwrap().join("notes.txt"))
+      }
+      other => panic!("unexpected {:?}", other),
+    }
+  }
+
+  #[test]
+  fn rejects_relative_input() {
+    let inputs = [
+      "ark-drop-relative-test",
+      "./ark-drop-relative-test",
+      "../ark-drop-relative-test",
+      "~other/ark-drop-relative-test",
+    ];
+    for input in inputs {
+      match resolve_directory(input, true) {
+        Err(DownloadDirError::NotAbsolute { path }) => assert_eq!(path, Path::new(input)),
+        other => panic!("unexpected {:?} for {:?}", other, input),
+      }
+      assert!(!Path::new(input).exists(), "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn resolves_parent_components_in_absolute_input() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("transfers")).unwrap();
+
+    let input = dir.path().join("transfers/../transfers");
+    let resolved = resolve_directory(input.to_str().unwrap(), false).unwrap();
+    assert_eq!(resolved, dir.path().canonicalize().unwrap().join("transfers"));
+  }
+}
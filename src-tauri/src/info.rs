@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+/// Version and build metadata shown on the About page and attached to bug
+/// reports.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+  pub version: &'static str,
+  pub git_commit: &'static str,
+  /// Seconds since the Unix epoch at which the backend was compiled.
+  pub build_timestamp: u64,
+  pub tauri_version: &'static str,
+  pub os: &'static str,
+  pub arch: &'static str,
+}
+
+impl AppInfo {
+  pub fn current() -> Self {
+    Self {
+      version: env!("CARGO_PKG_VERSION"),
+      git_commit: env!("ARK_DROP_GIT_COMMIT"),
+      build_timestamp: env!("ARK_DROP_BUILD_TIMESTAMP").parse().unwrap_or_default(),
+      tauri_version: tauri::VERSION,
+      os: std::env::consts::OS,
+      arch: std::env::consts::ARCH,
+    }
+  }
+}
+
+#[tauri::command]
+pub fn get_app_info() -> AppInfo {
+  AppInfo::current()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::AppInfo;
+
+  #[test]
+  fn every_field_is_filled_in() {
+    let info = AppInfo::current();
+    let text_fields = [
+      ("version", info.version),
+      ("gitCommit", info.git_commit),
+      ("tauriVersion", info.tauri_version),
+      ("os", info.os),
+      ("arch", info.arch),
+    ];
+    for (name, value) in text_fields {
+      assert!(!value.trim().is_empty(), "{} is empty", name);
+    }
+    assert!(info.build_timestamp > 0);
+  }
+}
@@ -0,0 +1,374 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::config::{self, EnvConfig};
+use crate::settings::{Settings, SettingsStore};
+
+const LOG_FILE_NAME: &str = "ark-drop.log";
+const MAX_LOG_FILE_MB: u64 = 100;
+const MAX_KEPT_LOG_FILES: usize = 20;
+
+/// Log file writer that starts a fresh file once the current one would grow
+/// past `max_bytes`, keeping at most `keep` rotated files (`ark-drop.log.1`
+/// being the most recent).
+#[derive(Clone)]
+pub struct RotatingFile {
+  inner: Arc<Mutex<RotatingFileInner>>,
+}
+
+struct RotatingFileInner {
+  dir: PathBuf,
+  file: File,
+  size: u64,
+  max_bytes: u64,
+  keep: usize,
+}
+
+impl RotatingFile {
+  pub fn open(dir: &Path, max_bytes: u64, keep: usize) -> io::Result<Self> {
+    fs::create_dir_all(dir)?;
+    let file = open_append(&dir.join(LOG_FILE_NAME))?;
+    let size = file.metadata()?.len();
+
+    Ok(Self {
+      inner: Arc::new(Mutex::new(RotatingFileInner {
+        dir: dir.to_path_buf(),
+        file,
+        size,
+        max_bytes,
+        keep,
+      })),
+    })
+  }
+
+  pub fn keep(&self) -> usize {
+    self.lock().keep
+  }
+
+  /// Changes the rotation limits, deleting rotated files that are no longer
+  /// kept.
+  pub fn set_limits(&self, max_bytes: u64, keep: usize) -> io::Result<()> {
+    let mut inner = self.lock();
+    for index in keep + 1..=inner.keep {
+      remove_if_exists(&rotated_path(&inner.dir, index))?;
+    }
+    inner.max_bytes = max_bytes;
+    inner.keep = keep;
+    Ok(())
+  }
+
+  /// Flushes buffered output and asks the OS to write the file to disk.
+  pub fn sync(&self) -> io::Result<()> {
+    let mut inner = self.lock();
+    inner.file.flush()?;
+    inner.file.sync_all()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, RotatingFileInner> {
+    self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+impl RotatingFileInner {
+  fn rotate(&mut self) -> io::Result<()> {
+    self.file.flush()?;
+    let current = self.dir.join(LOG_FILE_NAME);
+
+    if self.keep == 0 {
+      fs::remove_file(&current)?;
+    } else {
+      remove_if_exists(&rotated_path(&self.dir, self.keep))?;
+      for index in (1..self.keep).rev() {
+        let from = rotated_path(&self.dir, index);
+        if from.exists() {
+          fs::rename(from, rotated_path(&self.dir, index + 1))?;
+        }
+      }
+      fs::rename(&current, rotated_path(&self.dir, 1))?;
+    }
+
+    self.file = open_append(&current)?;
+    self.size = 0;
+    Ok(())
+  }
+}
+
+impl Write for RotatingFile {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let mut inner = self.lock();
+    if inner.size > 0 && inner.size + buf.len() as u64 > inner.max_bytes {
+      inner.rotate()?;
+    }
+    let written = inner.file.write(buf)?;
+    inner.size += written as u64;
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.lock().file.flush()
+  }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+  OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+  dir.join(format!("{}.{}", LOG_FILE_NAME, index))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+  match fs::remove_file(path) {
+    Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+    _ => Ok(()),
+  }
+}
+
+pub fn parse_level(value: &str) -> Result<LevelFilter, String> {
+  value
+    .trim()
+    .parse()
+    .map_err(|_| format!("Unknown log level '{}'", value))
+}
+
+/// The global subscriber installed by [`init`] and the file it writes to.
+pub struct FileLogging {
+  dir: PathBuf,
+  writer: RotatingFile,
+  level: reload::Handle<LevelFilter, Registry>,
+}
+
+impl FileLogging {
+  /// Directory holding the current and rotated log files.
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// Path of the file currently being written to.
+  pub fn current_file(&self) -> PathBuf {
+    self.dir.join(LOG_FILE_NAME)
+  }
+
+  /// Current and rotated log files, newest first.
+  pub fn files(&self) -> Vec<PathBuf> {
+    let rotated = (1..=self.writer.keep()).map(|index| rotated_path(&self.dir, index));
+    std::iter::once(self.current_file()).chain(rotated).collect()
+  }
+
+  pub fn set_level(&self, level: LevelFilter) -> Result<(), String> {
+    self.level.reload(level).map_err(|error| error.to_string())
+  }
+}
+
+/// Managed state for logging. File logging is unavailable when the log file
+/// couldn't be set up, and the log commands then report why.
+pub struct Logging(Result<FileLogging, String>);
+
+impl Logging {
+  pub fn file(&self) -> Result<&FileLogging, String> {
+    self
+      .0
+      .as_ref()
+      .map_err(|error| format!("File logging is unavailable: {}", error))
+  }
+}
+
+/// Installs the global tracing subscriber at `level`, writing to a log file in
+/// `dir` that is rotated according to the persisted retention settings.
+pub fn init(dir: &Path, settings: &Settings, level: LevelFilter) -> io::Result<FileLogging> {
+  let max_file_mb = settings.log_max_file_mb.clamp(1, MAX_LOG_FILE_MB);
+  let kept_files = settings.log_kept_files.min(MAX_KEPT_LOG_FILES);
+  let writer = RotatingFile::open(dir, max_file_mb * 1024 * 1024, kept_files)?;
+  let (filter, handle) = reload::Layer::new(level);
+  let file_writer = writer.clone();
+
+  tracing_subscriber::registry()
+    .with(filter)
+    .with(
+      fmt::layer()
+        .with_ansi(false)
+        .with_writer(move || file_writer.clone()),
+    )
+    .try_init()
+    .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+
+  Ok(FileLogging {
+    dir: dir.to_path_buf(),
+    writer,
+    level: handle,
+  })
+}
+
+/// Like [`init`], but falls back to logging to stderr when the log file can't
+/// be set up. A broken log directory is not worth refusing to start over.
+pub fn init_or_fallback(dir: &Path, settings: &Settings, level: LevelFilter) -> Logging {
+  match init(dir, settings, level) {
+    Ok(file) => Logging(Ok(file)),
+    Err(error) => {
+      init_stderr(level);
+      tracing::error!(%error, dir = %dir.display(), "file logging is unavailable");
+      Logging(Err(error.to_string()))
+    }
+  }
+}
+
+/// Installs a subscriber that only writes to stderr. Does nothing if a
+/// subscriber is already installed.
+fn init_stderr(level: LevelFilter) {
+  let _ = tracing_subscriber::registry()
+    .with(level)
+    .with(fmt::layer().with_writer(io::stderr))
+    .try_init();
+}
+
+#[tauri::command]
+pub fn set_log_level(
+  level: String,
+  settings: tauri::State<'_, SettingsStore>,
+  env: tauri::State<'_, EnvConfig>,
+  logging: tauri::State<'_, Logging>,
+) -> Result<(), String> {
+  if env.log_level.as_ref().map_or(false, |preset| preset.forced) {
+    return Err(format!("The log level is fixed by {}_FORCE", config::LOG_LEVEL_ENV));
+  }
+
+  let logging = logging.file()?;
+  let parsed = parse_level(&level)?;
+  settings.update(|current| current.log_level = Some(parsed.to_string().to_lowercase()))?;
+  logging.set_level(parsed)?;
+  tracing::info!(level = %parsed, "log level changed");
+  Ok(())
+}
+
+#[tauri::command]
+pub fn set_log_retention(
+  max_file_mb: u64,
+  kept_files: usize,
+  settings: tauri::State<'_, SettingsStore>,
+  logging: tauri::State<'_, Logging>,
+) -> Result<(), String> {
+  if max_file_mb == 0 || max_file_mb > MAX_LOG_FILE_MB {
+    return Err(format!("Log file size must be between 1 and {} MB", MAX_LOG_FILE_MB));
+  }
+  if kept_files > MAX_KEPT_LOG_FILES {
+    return Err(format!("At most {} old log files can be kept", MAX_KEPT_LOG_FILES));
+  }
+
+  let logging = logging.file()?;
+  settings.update(|current| {
+    current.log_max_file_mb = max_file_mb;
+    current.log_kept_files = kept_files;
+  })?;
+  logging
+    .writer
+    .set_limits(max_file_mb * 1024 * 1024, kept_files)
+    .map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub fn get_log_path(logging: tauri::State<'_, Logging>) -> Result<PathBuf, String> {
+  Ok(logging.file()?.current_file())
+}
+
+/// Makes sure everything logged so far is on disk, e.g. before the user
+/// attaches the log file to a bug report.
+#[tauri::command]
+pub fn flush_logs(logging: tauri::State<'_, Logging>) -> Result<(), String> {
+  logging.file()?.writer.sync().map_err(|error| error.to_string())
+}
+
+#[tauri::command]
+pub fn open_logs(logging: tauri::State<'_, Logging>) -> Result<(), String> {
+  let logging = logging.file()?;
+  logging.writer.sync().map_err(|error| error.to_string())?;
+  open::that(logging.dir()).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::io::Write;
+  use std::path::Path;
+
+  use tracing::level_filters::LevelFilter;
+
+  use super::{init_or_fallback, rotated_path, RotatingFile, LOG_FILE_NAME};
+  use crate::settings::Settings;
+
+  fn write_lines(writer: &mut RotatingFile, from: usize, to: usize) {
+    for index in from..to {
+      // 10 bytes per line.
+      writer.write_all(format!("line {:04}\n", index).as_bytes()).unwrap();
+    }
+    writer.flush().unwrap();
+  }
+
+  fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+  }
+
+  #[test]
+  fn rotates_and_trims_old_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut writer = RotatingFile::open(dir.path(), 30, 2).unwrap();
+    write_lines(&mut writer, 0, 12);
+
+    assert_eq!(read(&dir.path().join(LOG_FILE_NAME)), "line 0009\nline 0010\nline 0011\n");
+    assert_eq!(read(&rotated_path(dir.path(), 1)), "line 0006\nline 0007\nline 0008\n");
+    assert_eq!(read(&rotated_path(dir.path(), 2)), "line 0003\nline 0004\nline 0005\n");
+    assert!(!rotated_path(dir.path(), 3).exists());
+  }
+
+  #[test]
+  fn keeps_no_rotated_files_when_keep_is_zero() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut writer = RotatingFile::open(dir.path(), 20, 0).unwrap();
+    write_lines(&mut writer, 0, 5);
+
+    assert_eq!(read(&dir.path().join(LOG_FILE_NAME)), "line 0004\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+  }
+
+  #[test]
+  fn continues_existing_file_after_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    write_lines(&mut RotatingFile::open(dir.path(), 30, 2).unwrap(), 0, 2);
+    write_lines(&mut RotatingFile::open(dir.path(), 30, 2).unwrap(), 2, 4);
+
+    assert_eq!(read(&rotated_path(dir.path(), 1)), "line 0000\nline 0001\nline 0002\n");
+    assert_eq!(read(&dir.path().join(LOG_FILE_NAME)), "line 0003\n");
+  }
+
+  #[test]
+  fn lowering_keep_deletes_extra_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut writer = RotatingFile::open(dir.path(), 10, 4).unwrap();
+    write_lines(&mut writer, 0, 5);
+    assert!(rotated_path(dir.path(), 4).exists());
+
+    writer.set_limits(10, 1).unwrap();
+    assert!(rotated_path(dir.path(), 1).exists());
+    for index in 2..=4 {
+      assert!(!rotated_path(dir.path(), index).exists());
+    }
+    assert_eq!(writer.keep(), 1);
+  }
+
+  #[test]
+  fn falls_back_when_the_log_directory_is_unusable() {
+    let dir = tempfile::tempdir().unwrap();
+    // A file where the log directory should be.
+    let blocked = dir.path().join("logs");
+    fs::write(&blocked, "").unwrap();
+
+    let logging = init_or_fallback(&blocked, &Settings::default(), LevelFilter::INFO);
+    let error = logging.file().err().unwrap();
+    assert!(error.starts_with("File logging is unavailable: "), "{}", error);
+  }
+}
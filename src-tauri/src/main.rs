@@ -1,8 +1,94 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod archive;
+mod config;
+mod diagnostics;
+mod downloads;
+mod info;
+mod logging;
+mod pins;
+mod schemas;
+mod settings;
+mod startup;
+mod ticket;
+mod tree_size;
+
+use tauri::Manager;
+
+use crate::config::EnvConfig;
+use crate::settings::SettingsStore;
+use crate::startup::{PendingStartup, StartupAction};
+use crate::tree_size::TreeScans;
+
 fn main() {
   tauri::Builder::default()
+    .manage(TreeScans::default())
+    .setup(|app| {
+      let resolver = app.path_resolver();
+      let config_dir = resolver
+        .app_config_dir()
+        .ok_or("could not resolve the app config directory")?;
+      let log_dir = resolver
+        .app_log_dir()
+        .ok_or("could not resolve the app log directory")?;
+
+      let (env, env_errors) = EnvConfig::from_env();
+      let settings = SettingsStore::load(&config_dir);
+      let current = settings.get();
+      let (level, _) = config::effective_log_level(&current, &env);
+      let logging = logging::init_or_fallback(&log_dir, &current, level);
+      tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting ARK Drop");
+      if let Some(error) = settings.load_error() {
+        tracing::error!(%error, "using default settings");
+      }
+
+      let mut actions = Vec::new();
+      if !env_errors.is_empty() {
+        for error in &env_errors {
+          tracing::error!(%error, "ignoring invalid environment variable");
+        }
+        actions.push(StartupAction::Error {
+          message: format!("Ignored invalid environment variables: {}", env_errors.join("; ")),
+        });
+      }
+      match startup::parse_args(std::env::args_os().skip(1)) {
+        Ok(action) => actions.extend(action),
+        Err(message) => actions.push(StartupAction::Error { message }),
+      }
+
+      app.manage(env);
+      app.manage(settings);
+      app.manage(logging);
+      app.manage(PendingStartup::new(actions));
+      Ok(())
+    })
+    .invoke_handler(tauri::generate_handler![
+      archive::list_archive,
+      config::get_app_config,
+      config::get_effective_config,
+      diagnostics::export_logs,
+      diagnostics::get_recent_logs,
+      downloads::clear_download_directory,
+      downloads::get_download_dir_info,
+      downloads::get_download_directory,
+      downloads::set_download_directory,
+      info::get_app_info,
+      logging::flush_logs,
+      logging::get_log_path,
+      logging::open_logs,
+      logging::set_log_level,
+      logging::set_log_retention,
+      pins::list_pins,
+      pins::pin_path,
+      pins::reorder_pins,
+      pins::unpin_path,
+      settings::get_settings,
+      startup::frontend_ready,
+      ticket::normalize_ticket,
+      tree_size::cancel_tree_size,
+      tree_size::compute_tree_size
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
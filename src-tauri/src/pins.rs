@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::downloads::expand_home;
+use crate::settings::SettingsStore;
+
+/// Upper bound on the number of pinned folders and files.
+const MAX_PINS: usize = 20;
+
+/// A folder or file the user pinned as a quick-send source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pin {
+  pub id: u64,
+  pub path: PathBuf,
+  pub label: String,
+}
+
+/// A pin as listed in the UI, with `stale` set when its path has vanished.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinStatus {
+  #[serde(flatten)]
+  pub pin: Pin,
+  pub stale: bool,
+}
+
+/// Pins `path`, or returns the existing pin if it is already pinned. An empty
+/// label defaults to the file or folder name.
+pub fn pin(path: &str, label: &str, settings: &SettingsStore) -> Result<Pin, String> {
+  let path = expand_home(Path::new(path.trim()))?;
+  let path = path
+    .canonicalize()
+    .map_err(|error| format!("{} cannot be pinned: {}", path.display(), error))?;
+  let label = match label.trim() {
+    "" => path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_else(|| path.display().to_string()),
+    label => label.to_string(),
+  };
+
+  let pins = settings.get().pins;
+  if let Some(existing) = pins.iter().find(|pin| pin.path == path) {
+    return Ok(existing.clone());
+  }
+  if pins.len() >= MAX_PINS {
+    return Err(format!("At most {} items can be pinned", MAX_PINS));
+  }
+
+  let mut pin = Pin { id: 0, path, label };
+  settings.update(|current| {
+    // Stay above the ids in use even if the counter was edited out of the file.
+    let after_existing = current.pins.iter().map(|pin| pin.id + 1).max().unwrap_or(1);
+    pin.id = current.next_pin_id.max(after_existing);
+    current.next_pin_id = pin.id + 1;
+    current.pins.push(pin.clone());
+  })?;
+  Ok(pin)
+}
+
+pub fn unpin(id: u64, settings: &SettingsStore) -> Result<(), String> {
+  settings.update(|current| current.pins.retain(|pin| pin.id != id))?;
+  Ok(())
+}
+
+pub fn pin_statuses(settings: &SettingsStore) -> Vec<PinStatus> {
+  settings
+    .get()
+    .pins
+    .into_iter()
+    .map(|pin| PinStatus {
+      stale: !pin.path.exists(),
+      pin,
+    })
+    .collect()
+}
+
+/// Reorders the pins to match `ids`, which must list every pin exactly once.
+pub fn reorder(ids: &[u64], settings: &SettingsStore) -> Result<(), String> {
+  let mut pins = settings.get().pins;
+  let mut sorted = ids.to_vec();
+  sorted.sort_unstable();
+  sorted.dedup();
+  let mut known: Vec<u64> = pins.iter().map(|pin| pin.id).collect();
+  known.sort_unstable();
+  if sorted.len() != ids.len() || sorted != known {
+    return Err("The new order must list every pin exactly once".to_string());
+  }
+
+  pins.sort_by_key(|pin| ids.iter().position(|id| *id == pin.id));
+  settings.update(|current| current.pins = pins)?;
+  Ok(())
+}
+
+#[tauri::command]
+pub fn pin_path(
+  path: String,
+  label: String,
+  settings: tauri::State<'_, SettingsStore>,
+) -> Result<Pin, String> {
+  pin(&path, &label, &settings)
+}
+
+#[tauri::command]
+pub fn list_pins(settings: tauri::State<'_, SettingsStore>) -> Vec<PinStatus> {
+  pin_statuses(&settings)
+}
+
+#[tauri::command]
+pub fn unpin_path(id: u64, settings: tauri::State<'_, SettingsStore>) -> Result<(), String> {
+  unpin(id, &settings)
+}
+
+#[tauri::command]
+pub fn reorder_pins(
+  ids: Vec<u64>,
+  settings: tauri::State<'_, SettingsStore>,
+) -> Result<(), String> {
+  reorder(&ids, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::{Path, PathBuf};
+
+  use super::{pin, pin_statuses, reorder, unpin, MAX_PINS};
+  use crate::settings::SettingsStore;
+
+  fn touch(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, name).unwrap();
+    path
+  }
+
+  fn text(path: &Path) -> &str {
+    path.to_str().unwrap()
+  }
+
+  fn ids(settings: &SettingsStore) -> Vec<u64> {
+    settings.get().pins.iter().map(|pin| pin.id).collect()
+  }
+
+  #[test]
+  fn pins_persist_across_reloads() {
+    let config = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    let report = touch(files.path(), "report.pdf");
+    let photos = files.path().join("photos");
+    fs::create_dir(&photos).unwrap();
+
+    let settings = SettingsStore::load(config.path());
+    let first = pin(text(&report), "", &settings).unwrap();
+    let second = pin(text(&photos), "  Holiday  ", &settings).unwrap();
+    assert_eq!(first.label, "report.pdf");
+    assert_eq!(second.label, "Holiday");
+    assert_eq!(pin(text(&report), "other", &settings).unwrap().id, first.id);
+
+    let reloaded = SettingsStore::load(config.path()).get().pins;
+    assert_eq!(reloaded.len(), 2);
+    assert_eq!(reloaded[0].id, first.id);
+    assert_eq!(reloaded[0].path, report.canonicalize().unwrap());
+    assert_eq!(reloaded[1].label, "Holiday");
+  }
+
+  #[test]
+  fn ids_of_removed_pins_are_not_reused() {
+    let config = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    let settings = SettingsStore::load(config.path());
+    let first = pin(text(&touch(files.path(), "a.txt")), "", &settings).unwrap();
+    let second = pin(text(&touch(files.path(), "b.txt")), "", &settings).unwrap();
+
+    unpin(second.id, &settings).unwrap();
+    let third = pin(text(&touch(files.path(), "c.txt")), "", &settings).unwrap();
+    assert!(third.id > second.id);
+
+    unpin(third.id, &settings).unwrap();
+    let reloaded = SettingsStore::load(config.path());
+    let fourth = pin(text(&touch(files.path(), "d.txt")), "", &reloaded).unwrap();
+    assert!(fourth.id > third.id);
+    assert_eq!(ids(&reloaded), [first.id, fourth.id]);
+  }
+
+  #[test]
+  fn deleted_paths_are_stale() {
+    let config = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    let kept = touch(files.path(), "kept.txt");
+    let deleted = touch(files.path(), "deleted.txt");
+    let settings = SettingsStore::load(config.path());
+    pin(text(&kept), "", &settings).unwrap();
+    pin(text(&deleted), "", &settings).unwrap();
+
+    fs::remove_file(&deleted).unwrap();
+    let reloaded = SettingsStore::load(config.path());
+    let stale: Vec<(String, bool)> = pin_statuses(&reloaded)
+      .into_iter()
+      .map(|status| (status.pin.label, status.stale))
+      .collect();
+    assert_eq!(
+      stale,
+      [("kept.txt".to_string(), false), ("deleted.txt".to_string(), true)]
+    );
+  }
+
+  #[test]
+  fn pins_are_capped() {
+    let config = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    let settings = SettingsStore::load(config.path());
+    for index in 0..MAX_PINS {
+      let path = touch(files.path(), &format!("{}.txt", index));
+      pin(text(&path), "", &settings).unwrap();
+    }
+
+    // A refused pin must not touch the settings file.
+    let settings_file = config.path().join("settings.json");
+    fs::remove_file(&settings_file).unwrap();
+    let extra = touch(files.path(), "extra.txt");
+    assert_eq!(
+      pin(text(&extra), "", &settings).unwrap_err(),
+      format!("At most {} items can be pinned", MAX_PINS)
+    );
+    assert!(!settings_file.exists());
+    assert_eq!(settings.get().pins.len(), MAX_PINS);
+  }
+
+  #[test]
+  fn reorder_requires_every_pin_once() {
+    let config = tempfile::tempdir().unwrap();
+    let files = tempfile::tempdir().unwrap();
+    let settings = SettingsStore::load(config.path());
+    for name in ["a", "b", "c"] {
+      pin(text(&touch(files.path(), name)), "", &settings).unwrap();
+    }
+    assert_eq!(ids(&settings), [1, 2, 3]);
+
+    for invalid in [&[3, 1, 1][..], &[3, 1], &[3, 1, 2, 4], &[3, 1, 4], &[]] {
+      assert!(reorder(invalid, &settings).is_err(), "accepted {:?}", invalid);
+      assert_eq!(ids(&settings), [1, 2, 3]);
+    }
+
+    reorder(&[3, 1, 2], &settings).unwrap();
+    assert_eq!(ids(&settings), [3, 1, 2]);
+    assert_eq!(ids(&SettingsStore::load(config.path())), [3, 1, 2]);
+  }
+}
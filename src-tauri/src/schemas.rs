@@ -0,0 +1,233 @@
+use serde::Serialize;
+
+/// Version of the payload shapes sent to the frontend. Bump it whenever a
+/// field is renamed, removed or changes meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// An event payload serialized as its own fields plus `schemaVersion`, so the
+/// frontend can detect a backend it doesn't understand.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Versioned<T> {
+  pub schema_version: u32,
+  #[serde(flatten)]
+  pub payload: T,
+}
+
+impl<T> Versioned<T> {
+  pub fn new(payload: T) -> Self {
+    Self {
+      schema_version: SCHEMA_VERSION,
+      payload,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::{Path, PathBuf};
+
+  use serde::Serialize;
+
+  use super::Versioned;
+  use crate::archive::{ArchiveEntry, ArchiveListing};
+  use crate::config::{AppConfigError, ConfigSource, ConfigValue, EffectiveConfig};
+  use crate::diagnostics::LogEntry;
+  use crate::downloads::{DownloadDirError, DownloadDirInfo, DownloadDirSource};
+  use crate::info::AppInfo;
+  use crate::pins::{Pin, PinStatus};
+  use crate::startup::StartupAction;
+  use crate::tree_size::{TreeSize, TreeSizeProgress};
+
+  /// Compares `value` with the checked-in JSON in `tests/schemas/<name>`. Any
+  /// difference is a change to what the frontend receives.
+  fn assert_golden<T: Serialize>(name: &str, value: &T) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("tests/schemas")
+      .join(name);
+    let golden = fs::read_to_string(&path)
+      .unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+    let expected: serde_json::Value = serde_json::from_str(&golden).unwrap();
+    let actual = serde_json::to_value(value).unwrap();
+    assert_eq!(actual, expected, "{} no longer matches", name);
+  }
+
+  #[test]
+  fn tree_size_progress() {
+    let progress = TreeSizeProgress {
+      scan_id: "scan-1",
+      files: 3,
+      dirs: 1,
+      total_bytes: 4096,
+    };
+    assert_golden("tree_size_progress.json", &Versioned::new(progress));
+  }
+
+  #[test]
+  fn startup_action() {
+    let actions = vec![
+      Versioned::new(StartupAction::Send {
+        paths: vec![PathBuf::from("/home/user/report.pdf")],
+      }),
+      Versioned::new(StartupAction::Receive {
+        ticket: "abc123:4567".to_string(),
+      }),
+      Versioned::new(StartupAction::Error {
+        message: "--receive needs a ticket".to_string(),
+      }),
+    ];
+    assert_golden("startup_action.json", &actions);
+  }
+
+  #[test]
+  fn tree_size() {
+    let size = TreeSize {
+      files: 3,
+      dirs: 1,
+      total_bytes: 4096,
+      inaccessible: vec![PathBuf::from("/data/private")],
+      skipped_links: vec![PathBuf::from("/data/latest")],
+    };
+    assert_golden("tree_size.json", &size);
+  }
+
+  #[test]
+  fn download_dir_info() {
+    let info = DownloadDirInfo {
+      effective: PathBuf::from("/home/user/Downloads"),
+      source: DownloadDirSource::System,
+      system_default: Some(PathBuf::from("/home/user/Downloads")),
+      writable: true,
+    };
+    assert_golden("download_dir_info.json", &info);
+  }
+
+  #[test]
+  fn download_dir_error() {
+    let errors = vec![
+      DownloadDirError::NotAbsolute {
+        path: PathBuf::from("Downloads"),
+      },
+      DownloadDirError::Missing {
+        path: PathBuf::from("/mnt/usb"),
+      },
+      DownloadDirError::NotADirectory {
+        path: PathBuf::from("/home/user/notes.txt"),
+      },
+      DownloadDirError::ReadOnly {
+        path: PathBuf::from("/mnt/cdrom"),
+      },
+      DownloadDirError::PermissionDenied {
+        path: PathBuf::from("/root"),
+      },
+      DownloadDirError::Other {
+        path: PathBuf::from("/srv/drop"),
+        message: "disk quota exceeded".to_string(),
+      },
+    ];
+    assert_golden("download_dir_error.json", &errors);
+  }
+
+  #[test]
+  fn app_config_error() {
+    let errors = vec![
+      AppConfigError::NotAllowed {
+        key: "PATH".to_string(),
+      },
+      AppConfigError::Unavailable {
+        key: "log_level".to_string(),
+        message: "invalid value".to_string(),
+      },
+    ];
+    assert_golden("app_config_error.json", &errors);
+  }
+
+  #[test]
+  fn effective_config() {
+    let config = EffectiveConfig {
+      download_dir: Some(ConfigValue {
+        value: PathBuf::from("/srv/drop"),
+        source: ConfigSource::Forced,
+      }),
+      log_level: ConfigValue {
+        value: "info".to_string(),
+        source: ConfigSource::Default,
+      },
+    };
+    assert_golden("effective_config.json", &config);
+  }
+
+  #[test]
+  fn pin_status() {
+    let status = PinStatus {
+      pin: Pin {
+        id: 1,
+        path: PathBuf::from("/home/user/Projects"),
+        label: "Projects".to_string(),
+      },
+      stale: false,
+    };
+    assert_golden("pin_status.json", &status);
+  }
+
+  #[test]
+  fn log_entry() {
+    let entry = LogEntry {
+      timestamp: "2024-06-01T10:00:00.000000Z".to_string(),
+      level: "INFO".to_string(),
+      target: "app::logging".to_string(),
+      message: "log level changed".to_string(),
+    };
+    assert_golden("log_entry.json", &entry);
+  }
+
+  #[test]
+  fn archive_entry() {
+    let entries = vec![
+      ArchiveEntry {
+        name: "docs/readme.txt".to_string(),
+        is_dir: false,
+        size: 1024,
+        compressed_size: Some(512),
+        compression: "Deflated".to_string(),
+      },
+      ArchiveEntry {
+        name: "docs/".to_string(),
+        is_dir: true,
+        size: 0,
+        compressed_size: None,
+        compression: "gzip".to_string(),
+      },
+    ];
+    assert_golden("archive_entry.json", &entries);
+  }
+
+  #[test]
+  fn archive_listing() {
+    let listing = ArchiveListing {
+      entries: vec![ArchiveEntry {
+        name: "report.pdf".to_string(),
+        is_dir: false,
+        size: 2048,
+        compressed_size: None,
+        compression: "none".to_string(),
+      }],
+      truncated: true,
+    };
+    assert_golden("archive_listing.json", &listing);
+  }
+
+  #[test]
+  fn app_info() {
+    let info = AppInfo {
+      version: "0.1.0",
+      git_commit: "abc1234",
+      build_timestamp: 1_700_000_000,
+      tauri_version: "1.6.4",
+      os: "linux",
+      arch: "x86_64",
+    };
+    assert_golden("app_info.json", &info);
+  }
+}
@@ -0,0 +1,177 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+
+use crate::pins::Pin;
+
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// User preferences persisted as JSON in the app config directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+  /// Where received files are saved; the system downloads folder when unset.
+  pub download_dir: Option<PathBuf>,
+  /// Chosen log level; `info` unless preset through the environment when
+  /// unset.
+  pub log_level: Option<String>,
+  /// Size at which the log file is rotated.
+  pub log_max_file_mb: u64,
+  /// Number of rotated log files kept besides the current one.
+  pub log_kept_files: usize,
+  /// Quick-send folders and files, in the order the user arranged them.
+  pub pins: Vec<Pin>,
+  /// Id given to the next pin, so the id of a removed pin is never reused.
+  pub next_pin_id: u64,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      download_dir: None,
+      log_level: None,
+      log_max_file_mb: 5,
+      log_kept_files: 4,
+      pins: Vec::new(),
+      next_pin_id: 1,
+    }
+  }
+}
+
+/// Managed state holding the current settings and the file they are saved to.
+pub struct SettingsStore {
+  path: PathBuf,
+  settings: Mutex<Settings>,
+  load_error: Option<String>,
+  /// Set while a settings file that failed to load is still in place, so the
+  /// first save moves it aside instead of overwriting it.
+  keep_bad_file: AtomicBool,
+}
+
+impl SettingsStore {
+  /// Loads the settings saved in `dir`, falling back to the defaults when the
+  /// file is missing or cannot be read or parsed.
+  pub fn load(dir: &Path) -> Self {
+    let path = dir.join(SETTINGS_FILE_NAME);
+    let (settings, load_error) = match read(&path) {
+      Ok(settings) => (settings, None),
+      Err(error) => (Settings::default(), Some(error)),
+    };
+
+    Self {
+      path,
+      settings: Mutex::new(settings),
+      keep_bad_file: AtomicBool::new(load_error.is_some()),
+      load_error,
+    }
+  }
+
+  /// Why the saved settings couldn't be loaded, if they couldn't.
+  pub fn load_error(&self) -> Option<&str> {
+    self.load_error.as_deref()
+  }
+
+  pub fn get(&self) -> Settings {
+    self.lock().clone()
+  }
+
+  /// Applies `change` and writes the result to disk. The in-memory settings
+  /// are left untouched if saving fails.
+  pub fn update<F: FnOnce(&mut Settings)>(&self, change: F) -> Result<Settings, String> {
+    let mut current = self.lock();
+    let mut next = current.clone();
+    change(&mut next);
+    if self.keep_bad_file.load(Ordering::SeqCst) {
+      fs::rename(&self.path, self.path.with_extension("json.bad"))
+        .map_err(|error| format!("Failed to move aside unreadable settings: {}", error))?;
+      self.keep_bad_file.store(false, Ordering::SeqCst);
+    }
+    save(&self.path, &next).map_err(|error| format!("Failed to save settings: {}", error))?;
+    *current = next.clone();
+    Ok(next)
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Settings> {
+    self.settings.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+/// Reads the settings file, treating a missing one as the defaults.
+fn read(path: &Path) -> Result<Settings, String> {
+  match fs::read(path) {
+    Ok(bytes) => serde_json::from_slice(&bytes)
+      .map_err(|error| format!("Failed to parse {}: {}", path.display(), error)),
+    Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+    Err(error) => Err(format!("Failed to read {}: {}", path.display(), error)),
+  }
+}
+
+fn save(path: &Path, settings: &Settings) -> io::Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let staging = path.with_extension("json.tmp");
+  fs::write(&staging, serde_json::to_vec_pretty(settings)?)?;
+  fs::rename(staging, path)
+}
+
+#[tauri::command]
+pub fn get_settings(settings: tauri::State<'_, SettingsStore>) -> Settings {
+  settings.get()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use super::{SettingsStore, SETTINGS_FILE_NAME};
+
+  #[test]
+  fn saves_camel_case_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SettingsStore::load(dir.path());
+    store
+      .update(|current| current.log_level = Some("warn".to_string()))
+      .unwrap();
+
+    let saved: serde_json::Value =
+      serde_json::from_slice(&fs::read(dir.path().join(SETTINGS_FILE_NAME)).unwrap()).unwrap();
+    assert_eq!(saved["logLevel"], "warn");
+    assert_eq!(SettingsStore::load(dir.path()).get().log_level.as_deref(), Some("warn"));
+  }
+
+  #[test]
+  fn moves_unreadable_settings_aside_before_saving() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(SETTINGS_FILE_NAME);
+    fs::write(&path, "{ not json").unwrap();
+
+    let store = SettingsStore::load(dir.path());
+    assert!(store.load_error().unwrap().starts_with("Failed to parse"));
+    assert_eq!(store.get().log_max_file_mb, 5);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+
+    store
+      .update(|current| current.log_level = Some("warn".to_string()))
+      .unwrap();
+    assert_eq!(
+      fs::read_to_string(dir.path().join("settings.json.bad")).unwrap(),
+      "{ not json"
+    );
+    assert_eq!(SettingsStore::load(dir.path()).get().log_level.as_deref(), Some("warn"));
+  }
+
+  #[test]
+  fn missing_settings_are_not_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SettingsStore::load(dir.path());
+    assert_eq!(store.load_error(), None);
+
+    store.update(|current| current.log_kept_files = 1).unwrap();
+    assert!(!dir.path().join("settings.json.bad").exists());
+  }
+}
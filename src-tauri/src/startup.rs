@@ -0,0 +1,243 @@
+use std::ffi::{OsStr, OsString};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use serde::Serialize;
+
+use crate::schemas::Versioned;
+use crate::ticket;
+
+/// Flow requested on the command line, e.g. `ark-drop --send ./report.pdf`
+/// or `ark-drop --receive <ticket>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StartupAction {
+  Send { paths: Vec<PathBuf> },
+  Receive { ticket: String },
+  /// The arguments were invalid; shown to the user instead of starting a flow.
+  Error { message: String },
+}
+
+/// Parses the command line (without the program name). Unknown options and
+/// stray arguments are an error so that a typo doesn't silently start an idle
+/// app; only the arguments platforms add when launching apps are ignored.
+pub fn parse_args<I>(args: I) -> Result<Option<StartupAction>, String>
+where
+  I: IntoIterator<Item = OsString>,
+{
+  let mut args = args.into_iter().peekable();
+  let mut action = None;
+
+  while let Some(arg) = args.next() {
+    let parsed = match arg.to_str() {
+      Some("--send") => {
+        let mut paths = Vec::new();
+        while let Some(path) = args.next_if(|next| !next.to_string_lossy().starts_with("--")) {
+          paths.push(PathBuf::from(path));
+        }
+        StartupAction::Send {
+          paths: resolve_paths(paths)?,
+        }
+      }
+      Some("--receive") => {
+        let input = args
+          .next()
+          .ok_or_else(|| "--receive needs a ticket".to_string())?;
+        let input = input
+          .to_str()
+          .ok_or_else(|| "The ticket passed to --receive is not valid text".to_string())?;
+        StartupAction::Receive {
+          ticket: ticket::normalize(input)?,
+        }
+      }
+      _ if is_platform_arg(&arg) => {
+        tracing::debug!(?arg, "ignoring platform argument");
+        continue;
+      }
+      _ if arg.to_string_lossy().starts_with('-') => {
+        return Err(format!("Unknown option {}", arg.to_string_lossy()));
+      }
+      _ => return Err(format!("Unexpected argument {}", arg.to_string_lossy())),
+    };
+
+    if action.is_some() {
+      return Err("Only one of --send or --receive can be given".to_string());
+    }
+    action = Some(parsed);
+  }
+  Ok(action)
+}
+
+/// Arguments added by the platform rather than the user, such as the process
+/// serial number macOS passes to apps started from Finder.
+fn is_platform_arg(arg: &OsStr) -> bool {
+  arg.to_string_lossy().starts_with("-psn_")
+}
+
+/// Makes every path absolute, reporting all the ones that can't be found at
+/// once.
+fn resolve_paths(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+  if paths.is_empty() {
+    return Err("--send needs at least one file or folder".to_string());
+  }
+
+  let mut resolved = Vec::with_capacity(paths.len());
+  let mut missing = Vec::new();
+  for path in paths {
+    match path.canonicalize() {
+      Ok(path) => resolved.push(path),
+      Err(_) => missing.push(path.display().to_string()),
+    }
+  }
+
+  if missing.is_empty() {
+    Ok(resolved)
+  } else {
+    Err(format!("Cannot send, not found: {}", missing.join(", ")))
+  }
+}
+
+/// Startup actions waiting for the webview to be ready to act on them.
+pub struct PendingStartup(Mutex<Vec<StartupAction>>);
+
+impl PendingStartup {
+  pub fn new(actions: Vec<StartupAction>) -> Self {
+    Self(Mutex::new(actions))
+  }
+
+  fn take(&self) -> Vec<StartupAction> {
+    mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+  }
+}
+
+/// Called by the frontend once it can handle events. Emits each pending
+/// startup action, in order, as `startup_action`; later calls emit nothing.
+#[tauri::command]
+pub fn frontend_ready(
+  window: tauri::Window,
+  pending: tauri::State<'_, PendingStartup>,
+) -> Result<(), String> {
+  for action in pending.take() {
+    window
+      .emit("startup_action", Versioned::new(action))
+      .map_err(|error| error.to_string())?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::ffi::OsString;
+  use std::fs;
+
+  use super::{parse_args, PendingStartup, StartupAction};
+
+  fn parse(args: &[&str]) -> Result<Option<StartupAction>, String> {
+    parse_args(args.iter().map(OsString::from))
+  }
+
+  #[test]
+  fn no_arguments_start_nothing() {
+    assert_eq!(parse(&[]), Ok(None));
+  }
+
+  #[test]
+  fn send_resolves_every_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("report.pdf");
+    fs::write(&file, "pdf").unwrap();
+
+    let action = parse(&["--send", file.to_str().unwrap(), dir.path().to_str().unwrap()]);
+    assert_eq!(
+      action,
+      Ok(Some(StartupAction::Send {
+        paths: vec![file.canonicalize().unwrap(), dir.path().canonicalize().unwrap()],
+      }))
+    );
+  }
+
+  #[test]
+  fn send_reports_missing_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("gone.txt");
+    let error = parse(&["--send", missing.to_str().unwrap()]).unwrap_err();
+    assert!(error.starts_with("Cannot send, not found:"), "{}", error);
+    assert!(error.contains("gone.txt"), "{}", error);
+
+    assert_eq!(
+      parse(&["--send"]),
+      Err("--send needs at least one file or folder".to_string())
+    );
+  }
+
+  #[test]
+  fn receive_normalizes_the_ticket() {
+    assert_eq!(
+      parse(&["--receive", " 'ark-drop://abc123:4567/' "]),
+      Ok(Some(StartupAction::Receive {
+        ticket: "abc123:4567".to_string(),
+      }))
+    );
+    assert_eq!(parse(&["--receive"]), Err("--receive needs a ticket".to_string()));
+    assert!(parse(&["--receive", "abc123"]).is_err());
+  }
+
+  #[test]
+  fn only_one_flow_can_be_requested() {
+    let dir = tempfile::tempdir().unwrap();
+    let args = ["--send", dir.path().to_str().unwrap(), "--receive", "abc123:4567"];
+    assert_eq!(
+      parse(&args),
+      Err("Only one of --send or --receive can be given".to_string())
+    );
+  }
+
+  #[test]
+  fn unknown_options_are_errors() {
+    assert_eq!(
+      parse(&["--recieve", "abc123:4567"]),
+      Err("Unknown option --recieve".to_string())
+    );
+    assert_eq!(parse(&["-r"]), Err("Unknown option -r".to_string()));
+  }
+
+  #[test]
+  fn stray_arguments_are_errors() {
+    assert_eq!(
+      parse(&["report.pdf"]),
+      Err("Unexpected argument report.pdf".to_string())
+    );
+    assert_eq!(
+      parse(&["--receive", "abc123:4567", "extra"]),
+      Err("Unexpected argument extra".to_string())
+    );
+  }
+
+  #[test]
+  fn platform_arguments_are_ignored() {
+    assert_eq!(parse(&["-psn_0_123456"]), Ok(None));
+    assert_eq!(
+      parse(&["-psn_0_123456", "--receive", "abc123:4567"]),
+      Ok(Some(StartupAction::Receive {
+        ticket: "abc123:4567".to_string(),
+      }))
+    );
+  }
+
+  #[test]
+  fn pending_action_is_taken_once() {
+    let actions = vec![
+      StartupAction::Error {
+        message: "bad".to_string(),
+      },
+      StartupAction::Receive {
+        ticket: "abc123:4567".to_string(),
+      },
+    ];
+    let pending = PendingStartup::new(actions.clone());
+    assert_eq!(pending.take(), actions);
+    assert_eq!(pending.take(), []);
+    assert_eq!(PendingStartup::new(Vec::new()).take(), []);
+  }
+}
@@ -0,0 +1,150 @@
+/// URL scheme used by share links and QR codes.
+const URL_SCHEME: &str = "ark-drop://";
+
+/// Longest input accepted before any other processing. Real tickets are far
+/// shorter; anything bigger is rejected outright rather than scanned.
+const MAX_INPUT_LEN: usize = 8 * 1024;
+
+/// Turns whatever the user pasted into the canonical `ticket:confirmation`
+/// form.
+///
+/// Surrounding whitespace and quotes are dropped, as is an `ark-drop://`
+/// wrapper. The result must be a non-empty alphanumeric ticket followed by a
+/// numeric confirmation code.
+pub fn normalize(input: &str) -> Result<String, String> {
+  if input.len() > MAX_INPUT_LEN {
+    return Err("Ticket is too long".to_string());
+  }
+
+  let mut value = input.trim();
+  loop {
+    let unquoted = strip_quotes(value).trim();
+    if unquoted.len() == value.len() {
+      break;
+    }
+    value = unquoted;
+  }
+
+  if value.len() >= URL_SCHEME.len()
+    && value.is_char_boundary(URL_SCHEME.len())
+    && value[..URL_SCHEME.len()].eq_ignore_ascii_case(URL_SCHEME)
+  {
+    value = value[URL_SCHEME.len()..].trim_matches('/');
+  }
+
+  let (ticket, confirmation) = value
+    .rsplit_once(':')
+    .ok_or_else(|| "Ticket is missing its confirmation code".to_string())?;
+
+  if ticket.is_empty() || !ticket.chars().all(|c| c.is_ascii_alphanumeric()) {
+    return Err("Ticket is malformed".to_string());
+  }
+  if confirmation.is_empty() || !confirmation.chars().all(|c| c.is_ascii_digit()) {
+    return Err("Confirmation code must be numeric".to_string());
+  }
+
+  Ok(format!("{}:{}", ticket, confirmation))
+}
+
+fn strip_quotes(value: &str) -> &str {
+  for quote in ['"', '\'', '`'] {
+    if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+      return &value[1..value.len() - 1];
+    }
+  }
+  value
+}
+
+#[cfg(not(fuzzing))]
+#[tauri::command]
+pub fn normalize_ticket(input: String) -> Result<String, String> {
+  normalize(&input).map_err(|error| {
+    tracing::debug!(%error, "rejected pasted ticket");
+    error
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{normalize, MAX_INPUT_LEN};
+
+  #[test]
+  fn rejects_input_over_the_length_cap() {
+    let input = format!("abc:{}", "1".repeat(MAX_INPUT_LEN + 1 - 4));
+    assert_eq!(input.len(), MAX_INPUT_LEN + 1);
+    assert_eq!(normalize(&input), Err("Ticket is too long".to_string()));
+  }
+
+  #[test]
+  fn unwraps_deeply_nested_mixed_quotes() {
+    let mut input = "abc123:4567".to_string();
+    for quote in ['"', '\'', '`'].iter().cycle().take(1000) {
+      input = format!("{} {} {}", quote, input, quote);
+    }
+    assert_eq!(normalize(&input), Ok("abc123:4567".to_string()));
+  }
+
+  #[test]
+  fn rejects_multibyte_characters_after_the_scheme() {
+    assert!(normalize("ark-drop:\u{e9}\u{e9}abc:1234").is_err());
+    assert!(normalize("ark-drop:/\u{1f600}abc:1234").is_err());
+    assert!(normalize("ark-drop\u{2014}//abc:1234").is_err());
+  }
+
+  #[test]
+  fn rejects_degenerate_input() {
+    for input in ["ark-drop://", ":", "\u{0}", "", "\"\"", "ark-drop://:"] {
+      assert!(normalize(input).is_err(), "accepted {:?}", input);
+    }
+  }
+
+  #[test]
+  fn accepts_messy_pastes() {
+    let cases = [
+      "abc123:4567",
+      "  abc123:4567\n",
+      "\t abc123:4567 \r\n",
+      "\"abc123:4567\"",
+      "'abc123:4567'",
+      "`abc123:4567`",
+      "\" 'abc123:4567' \"",
+      "`\"'abc123:4567'\"`",
+      "ark-drop://abc123:4567",
+      "ARK-DROP://abc123:4567///",
+      "Ark-Drop:///abc123:4567/",
+      " \"ark-drop://abc123:4567/\" ",
+    ];
+    for input in cases {
+      assert_eq!(normalize(input), Ok("abc123:4567".to_string()), "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn rejects_missing_or_bad_confirmation() {
+    let missing = Err("Ticket is missing its confirmation code".to_string());
+    let not_numeric = Err("Confirmation code must be numeric".to_string());
+    assert_eq!(normalize("abc123"), missing);
+    assert_eq!(normalize("ark-drop://abc123/"), missing);
+    assert_eq!(normalize("abc123:"), not_numeric);
+    assert_eq!(normalize("abc123:12a4"), not_numeric);
+    assert_eq!(normalize("abc123: 4567"), not_numeric);
+  }
+
+  #[test]
+  fn rejects_non_alphanumeric_ticket() {
+    for input in [":4567", "abc-123:4567", "abc 123:4567", "abc:123:4567", "ab\u{e9}c:4567"] {
+      assert_eq!(
+        normalize(input),
+        Err("Ticket is malformed".to_string()),
+        "{:?}",
+        input
+      );
+    }
+  }
+
+  #[test]
+  fn leaves_mismatched_quotes_in_place() {
+    assert!(normalize("\"abc123:4567'").is_err());
+    assert!(normalize("'abc123:4567").is_err());
+  }
+}
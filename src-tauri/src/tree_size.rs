@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::schemas::Versioned;
+
+/// How often interim totals are emitted while walking a large tree.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Totals for a file or directory tree. Symlinks below the root to files count
+/// as the file they point to; those to directories are not followed, so cycles
+/// can't make the walk loop.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeSize {
+  pub files: u64,
+  pub dirs: u64,
+  pub total_bytes: u64,
+  /// Directories and files that couldn't be read and are missing from the
+  /// totals.
+  pub inaccessible: Vec<PathBuf>,
+  /// Links to directories that were not followed and are missing from the
+  /// totals.
+  pub skipped_links: Vec<PathBuf>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeSizeProgress<'a> {
+  pub scan_id: &'a str,
+  pub files: u64,
+  pub dirs: u64,
+  pub total_bytes: u64,
+}
+
+/// Cancellation flags of the scans currently running, keyed by the id the
+/// frontend chose.
+#[derive(Default)]
+pub struct TreeScans(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl TreeScans {
+  /// Registers a scan, refusing an id that is already running so that
+  /// cancelling one scan can't stop another.
+  fn start(&self, scan_id: &str) -> Result<Arc<AtomicBool>, String> {
+    let mut scans = self.lock();
+    if scans.contains_key(scan_id) {
+      return Err(format!("A scan with id {} is already running", scan_id));
+    }
+    let flag = Arc::new(AtomicBool::new(false));
+    scans.insert(scan_id.to_string(), flag.clone());
+    Ok(flag)
+  }
+
+  fn finish(&self, scan_id: &str) {
+    self.lock().remove(scan_id);
+  }
+
+  fn cancel(&self, scan_id: &str) -> bool {
+    match self.lock().get(scan_id) {
+      Some(flag) => {
+        flag.store(true, Ordering::Relaxed);
+        true
+      }
+      None => false,
+    }
+  }
+
+  fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+    self.0.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+/// Walks `root`, calling `progress` with the running totals every
+/// [`PROGRESS_INTERVAL`]. Returns `None` if `cancelled` was set.
+pub fn walk<F>(root: &Path, cancelled: &AtomicBool, mut progress: F) -> Option<TreeSize>
+where
+  F: FnMut(&TreeSize),
+{
+  let mut size = TreeSize::default();
+  let mut pending = vec![root.to_path_buf()];
+  let mut last_report = Instant::now();
+  // The dropped item itself may be a link, such as /tmp on macOS, so the root
+  // is the one path that is followed.
+  let mut follow = true;
+
+  while let Some(path) = pending.pop() {
+    if cancelled.load(Ordering::Relaxed) {
+      return None;
+    }
+
+    let metadata = if std::mem::take(&mut follow) {
+      fs::metadata(&path)
+    } else {
+      fs::symlink_metadata(&path)
+    };
+    let (metadata, is_link) = match metadata {
+      Ok(metadata) if metadata.file_type().is_symlink() => (fs::metadata(&path), true),
+      other => (other, false),
+    };
+    let metadata = match metadata {
+      Ok(metadata) => metadata,
+      Err(_) => {
+        size.inaccessible.push(path);
+        continue;
+      }
+    };
+
+    if metadata.is_file() {
+      size.files += 1;
+      size.total_bytes += metadata.len();
+    } else if metadata.is_dir() && is_link {
+      size.skipped_links.push(path);
+    } else if metadata.is_dir() {
+      match fs::read_dir(&path) {
+        Ok(entries) => {
+          size.dirs += 1;
+          for entry in entries {
+            match entry {
+              Ok(entry) => pending.push(entry.path()),
+              // The entry's name is unknown, so blame the directory.
+              Err(_) if size.inaccessible.last() != Some(&path) => {
+                size.inaccessible.push(path.clone())
+              }
+              Err(_) => {}
+            }
+          }
+        }
+        Err(_) => size.inaccessible.push(path),
+      }
+    }
+
+    if last_report.elapsed() >= PROGRESS_INTERVAL {
+      progress(&size);
+      last_report = Instant::now();
+    }
+  }
+  Some(size)
+}
+
+/// Computes the size of `path` for the send screen. Interim totals are
+/// emitted as `tree_size_progress` events tagged with `scan_id`, which can
+/// also be passed to [`cancel_tree_size`].
+#[tauri::command]
+pub async fn compute_tree_size(
+  path: PathBuf,
+  scan_id: String,
+  window: tauri::Window,
+  scans: tauri::State<'_, TreeScans>,
+) -> Result<TreeSize, String> {
+  if fs::symlink_metadata(&path).is_err() {
+    return Err(format!("{} does not exist", path.display()));
+  }
+
+  let cancelled = scans.start(&scan_id)?;
+  let id = scan_id.clone();
+  let result = tauri::async_runtime::spawn_blocking(move || {
+    walk(&path, &cancelled, |size| {
+      let _ = window.emit(
+        "tree_size_progress",
+        Versioned::new(TreeSizeProgress {
+          scan_id: &id,
+          files: size.files,
+          dirs: size.dirs,
+          total_bytes: size.total_bytes,
+        }),
+      );
+    })
+  })
+  .await;
+  scans.finish(&scan_id);
+
+  result
+    .map_err(|error| error.to_string())?
+    .ok_or_else(|| "Cancelled".to_string())
+}
+
+/// Stops a running [`compute_tree_size`]. Returns whether a scan with that id
+/// was running.
+#[tauri::command]
+pub fn cancel_tree_size(scan_id: String, scans: tauri::State<'_, TreeScans>) -> bool {
+  scans.cancel(&scan_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::Path;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  use super::{walk, TreeScans, TreeSize};
+
+  /// `root/a.txt` (3 bytes), `root/sub/b.txt` (5 bytes) and an empty
+  /// `root/sub/deeper`.
+  fn fixture(root: &Path) {
+    fs::create_dir_all(root.join("sub/deeper")).unwrap();
+    fs::write(root.join("a.txt"), "abc").unwrap();
+    fs::write(root.join("sub/b.txt"), "hello").unwrap();
+  }
+
+  fn scan(root: &Path) -> TreeSize {
+    walk(root, &AtomicBool::new(false), |_| {}).unwrap()
+  }
+
+  #[test]
+  fn totals_a_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+
+    let size = scan(dir.path());
+    assert_eq!((size.files, size.dirs, size.total_bytes), (2, 3, 8));
+    assert!(size.inaccessible.is_empty());
+  }
+
+  #[test]
+  fn totals_a_single_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+
+    let size = scan(&dir.path().join("sub/b.txt"));
+    assert_eq!((size.files, size.dirs, size.total_bytes), (1, 0, 5));
+  }
+
+  #[test]
+  fn reports_missing_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing");
+
+    let size = scan(&missing);
+    assert_eq!(size.inaccessible, vec![missing]);
+  }
+
+  #[test]
+  fn stops_when_cancelled() {
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+    assert!(walk(dir.path(), &AtomicBool::new(true), |_| {}).is_none());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn follows_only_the_root_link() {
+    let dir = tempfile::tempdir().unwrap();
+    let tree = dir.path().join("tree");
+    fixture(&tree);
+    std::os::unix::fs::symlink(&tree, dir.path().join("link")).unwrap();
+    // A link back up inside the tree must not be followed.
+    std::os::unix::fs::symlink(&tree, tree.join("sub/loop")).unwrap();
+
+    let root = dir.path().join("link");
+    let size = scan(&root);
+    assert_eq!((size.files, size.dirs, size.total_bytes), (2, 3, 8));
+    assert!(size.inaccessible.is_empty());
+    assert_eq!(size.skipped_links, vec![root.join("sub/loop")]);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn counts_links_to_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+    let outside = tempfile::tempdir().unwrap();
+    fs::write(outside.path().join("big.bin"), "0123456789").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("big.bin"), dir.path().join("big")).unwrap();
+    let broken = dir.path().join("broken");
+    std::os::unix::fs::symlink(outside.path().join("gone"), &broken).unwrap();
+
+    let size = scan(dir.path());
+    assert_eq!((size.files, size.dirs, size.total_bytes), (3, 3, 18));
+    assert_eq!(size.inaccessible, vec![broken]);
+    assert!(size.skipped_links.is_empty());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn reports_unreadable_subdirectory() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+    let locked = dir.path().join("sub");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    let readable = fs::read_dir(&locked).is_ok();
+
+    let size = scan(dir.path());
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    // Permissions don't apply when running as root.
+    if readable {
+      return;
+    }
+    assert_eq!((size.files, size.dirs, size.total_bytes), (1, 1, 3));
+    assert_eq!(size.inaccessible, vec![locked]);
+  }
+
+  #[test]
+  fn scan_ids_are_unique_while_running() {
+    let scans = TreeScans::default();
+    let first = scans.start("scan-1").unwrap();
+    assert!(scans.start("scan-1").is_err());
+
+    let second = scans.start("scan-2").unwrap();
+    assert!(scans.cancel("scan-1"));
+    assert!(first.load(Ordering::Relaxed));
+    assert!(!second.load(Ordering::Relaxed));
+
+    scans.finish("scan-1");
+    assert!(!scans.cancel("scan-1"));
+    assert!(scans.start("scan-1").is_ok());
+  }
+}